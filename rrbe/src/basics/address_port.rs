@@ -0,0 +1,89 @@
+use redis::{Commands, ConnectionInfo, ConnectionLike};
+use rs_util::Config;
+use tracing::info;
+
+pub const ABOUT: &str = "Demo redis-rs for non-default connection info";
+
+fn show_description() {
+    println!("
+
+=====================================
+| Use a Specific Address, Port & DB |
+=====================================
+** Required **
+Redis server accessible at an IP address or resolvable hostname
+on a port other than 6379.
+
+-- Demonstration --
+1) Get the hostname or host's IP address from the command line
+2) Get the host's port from the command line
+3) Get the protocol from the command line
+4) Get the db number from the command line
+5) Construct the connection URL
+6) Connect to the redis server
+7) Display the connection information
+8) Write a value to a key
+9) Read the value from that key
+10) Display the key's value read from the server
+
+
+");
+}
+
+pub fn run(config: &Config) -> rs_util::Result<()> {
+    show_description();
+
+    // The host, port and db come from the command line, e.g.
+    //        ./rrbe-address-port --host 10.10.1.50 --port 7000 --db 0
+    //   or
+    //        rrbe --host 10.10.1.50 --port 7000 --db 0 basics address-port
+    // All options have defaults, so they are optional.
+    // The easiest way to test it is to run Redis in a container and change the exposed port.
+    // rs_util parses and validates them, so a bad port is reported instead of silently replaced.
+
+    // While it is concise and easy for a human to read a connection URL,
+    // e.g. redis://127.0.0.1:6379/0, redis-rs parses this URL into a
+    // ConnectionInfo struct. Instead of trying to construct the URL correctly
+    // from the command line arguments, only to have the URL parsed out again,
+    // we can simply construct the ConnectionInfo struct from the data directly.
+    let con_info = ConnectionInfo {
+        addr: redis::ConnectionAddr::Tcp(config.host.clone(), config.port),
+        redis: redis::RedisConnectionInfo {
+            db: config.db,
+            username: config.username.clone(),
+            password: config.password.clone(),
+        }
+    };
+
+    // ======== Everything that follows is identical to the getting-started example. ========
+    // Open connection to local redis server
+    let client = redis::Client::open(con_info)?;
+
+    // Display connection information
+    info!(addr = %client.get_connection_info().addr, "Connecting");
+    let mut con = client.get_connection()?;
+    //                   ^-- get_connection actually opens socket communication, whether over TCP or Unix sockets.
+    info!(open = con.is_open(), "Connection opened");
+
+    // Set key's value
+    let key_name = "addr:port:key";
+    let val = 42;
+    info!(key = key_name, val, "Setting the key");
+    let resp: String = con.set(key_name, val)?;
+    //        ^-- Tell it what type you'd like back.
+    //        |-- The "FromRedisValue" trait is implemented for most types.
+
+    info!(%resp, "The server answered SET");
+
+    // Get key's value and display it
+    let count: i32 = con.get(key_name)?;
+    // Display value of key
+    info!(key = key_name, value = count, "Read the key from the server");
+
+    // We should cleanup, so let's remove the key we created
+    info!("Cleaning up");
+    let _ : () = con.del(key_name)?;
+    //  ^-- Ignore the server's response, which is int(1), telling us how many keys were deleted successfully.
+    //  |-- If it fails, it will return a Result<Error>.
+    Ok(())
+}
@@ -0,0 +1,66 @@
+use redis::{Commands, ConnectionLike};
+use tracing::info;
+
+pub const ABOUT: &str = "Connect to a local server and set, get and delete a key";
+
+fn show_description() {
+    println!("
+
+===================
+| Getting Started |
+===================
+** Required **
+Redis server accessible at 127.0.0.1 on the default port, 6379.
+
+-- Demonstration --
+1) Connect to the redis server
+2) Display the connection information
+3) Write a value to a key
+4) Show the server's response to setting the key's value
+5) Read the value from that key
+6) Display the key's value read from the server
+
+
+");
+}
+
+/// Always connects to 127.0.0.1:6379, to show how a connection URL is put together
+pub fn run() -> rs_util::Result<()> {
+    show_description();
+
+    // Open connection to local redis server on default port
+    let client = redis::Client::open("redis://127.0.0.1:6379")?;
+    //                          ^     ^       ^         ^-- Port number
+    //                          |     |       |-- IP Address of redis server
+    //                          |     |-- Protocol to use -- One of: redis, rediss, unix, redis+unix
+    //                          |-- open() only validates the connection information and configures the connection.
+    //                          |-- No sockets are actually opened at this point.
+
+    // Display connection information
+    info!(addr = %client.get_connection_info().addr, "Connecting");
+    let mut con = client.get_connection()?;
+    //                   ^-- get_connection actually opens socket communication, whether over TCP or Unix sockets.
+    info!(open = con.is_open(), "Connection opened");
+
+    // Set key's value
+    let key_name = "getting:started:key";
+    let val = 42;
+    info!(key = key_name, val, "Setting the key");
+    let resp: String = con.set(key_name, val)?;
+    //        ^-- Tell it what type you'd like back.
+    //         -- The "FromRedisValue" trait is implemented for most types.
+
+    info!(%resp, "The server answered SET");
+
+    // Get key's value and display it
+    let count: i32 = con.get(key_name)?;
+    // Display value of key
+    info!(key = key_name, value = count, "Read the key from the server");
+
+    // We should cleanup, so let's remove the key we created
+    info!("Cleaning up");
+    let _ : () = con.del(key_name)?;
+    //  ^-- Ignore the server's response, which is int(1), telling us how many keys were deleted successfully.
+    //  |-- If it fails, it will return a Result<Error>.
+    Ok(())
+}
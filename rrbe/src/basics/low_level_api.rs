@@ -0,0 +1,133 @@
+use redis::{ConnectionInfo, ConnectionLike, Value, FromRedisValue};
+use rs_util::Config;
+use tracing::info;
+
+pub const ABOUT: &str = "Demo usage of redis-rs' low-level api";
+
+fn show_description() {
+    println!(
+        "
+
+======================
+| Demo Low-level API |
+======================
+** Required **
+Redis server accessible at an IP address or resolvable hostname
+
+-- Demonstration --
+1) If no Redis command is provided on the command line, 
+   > execute 'set low:level:api:key 128'
+   > execute 'get low:level:api:key'
+   > execute 'del low:level:api:key'
+2) If a Redis command is provided on the command line, execute it
+   using the low-level API provided by redis-rs.
+
+
+"
+    );
+}
+
+/// Run `user_cmd`, e.g. `["set", "mykey", "128"]`, or the hard-coded examples if it is empty
+pub fn run(config: &Config, user_cmd: &[&str]) -> rs_util::Result<()> {
+    show_description();
+
+    // The connection info and the command come from the command line, e.g.
+    //                ./rrbe-low-level-api --host 10.10.1.50 --port 7000 --db 0 set mykey 128
+    //       or
+    //                rrbe --host 10.10.1.50 --port 7000 --db 0 basics low-level-api set mykey 128
+    // All options have defaults, so they are optional.
+    // If no command is given, only the hard-coded examples will be run.
+
+    // While it is concise and easy for a human to read a connection URL,
+    // e.g. redis://127.0.0.1:6379/0, redis-rs parses this URL into a
+    // ConnectionInfo struct. Instead of trying to construct the URL correctly
+    // from the command line arguments, only to have the URL parsed out again,
+    // we can simply construct the ConnectionInfo struct from the data directly.
+    let con_info = ConnectionInfo {
+        addr: redis::ConnectionAddr::Tcp(config.host.clone(), config.port),
+        redis: redis::RedisConnectionInfo {
+            db: config.db,
+            username: config.username.clone(),
+            password: config.password.clone(),
+        }
+    };
+
+    // Open redis connection
+    let client = redis::Client::open(con_info)?;
+    //                          ^-- open() only validates the connection information and configures the connection.
+    //                          |-- No sockets are actually opened at this point.
+
+    // Display connection information
+    info!(addr = %client.get_connection_info().addr, "Connecting");
+    let mut con = client.get_connection()?;
+    //                   ^-- get_connection actually opens socket communication, whether over TCP or Unix sockets.
+    info!(open = con.is_open(), "Connection opened");
+
+    // If no command was provided on the command line, execute a few basic commands using the low-level API.
+    // This is useful in this example to demonstrate how the low-level command is constructed and then
+    // executed.
+    if user_cmd.is_empty() {
+        let key_name = "low:level:api:key";
+        let val = 128;
+        info!(key = key_name, val, "Setting the key");
+        let resp: String = redis::cmd("SET").arg(key_name).arg(val).query(&mut con)?;
+        //                 ^           ^     ^                      ^     ^-- Note that query() requires a mutable
+        //                 |           |     |                      |     |-- borrow of the connection
+        //                 |           |     |                      |-- Execute the command
+        //                 |           |     |-- args can are set after a command has been identified
+        //                 |           |     |-- args can be specified individually or as a vector of &str
+        //                 |           |-- Identify the Redis command
+        //                 |-- Use the low-level API.
+        //                 |-- The high-level API is syntactic sugar in that it is compiled down to the same exact
+        //                 |-- code used to execute the low-level API.
+        info!(%resp, "The server answered SET");
+
+        // Get key's value and display it
+        let count: i32 = redis::cmd("GET").arg(key_name).query(&mut con)?;
+        //         ^-- The library's FromRediValue trait is implemented for most types.
+        //         |-- This makes it easy to specify the desired return type.
+
+        // Display value of key
+        info!(key = key_name, value = count, "Read the key from the server");
+
+        // Let's clean up!
+        info!("Cleaning up");
+        let _ : () = redis::cmd("DEL").arg(key_name).query(&mut con)?;
+        //  ^-- Ignore the server's response, which is int(1), telling us how many keys were deleted successfully.
+        //  |-- If it fails, it will return a Result<Error>.
+
+        // If a command was given on the command line, execute it.
+    } else {
+        let resp = match redis::Cmd::new().arg(user_cmd).query(&mut con) {
+        //                           ^     ^-- The low-level API allows us to construct a Redis command from
+        //                           |     |-- a Vec<&str>. This allows us to reproduce the basic functionality
+        //                           |     |-- of the standard redis-cli with little effort.
+        //                           |-- Instead of using the syntatic sugar redis::cmd() method, we instantiate
+        //                           |-- a new Cmd struct, providing the entire command.
+
+            // If you're just getting started, these match conditions can be distracting.  It is not
+            // important to understand what is going on here in any great detail to get started.
+            // We need to match all the possible return types in order to reproduce the completely generic
+            // behavior of redis-cli.
+            // Most of the time, you will execute single commands and will know the expected return type.
+            // As demonstrated above, when you know the expected return type, or at least the type that is most
+            // convenient for you to get back, you can annotate the return type and allow the library's
+            // FromRedisValue trait implementations take care of the hard work for you.
+            Ok(Value::Int(val)) => val.to_string(),
+            Ok(Value::Nil) => "Nil".to_string(),
+            Ok(Value::Data(ref bytes)) => String::from_utf8_lossy(bytes).to_string(),
+            Ok(Value::Okay) => "OK".to_string(),
+            Ok(Value::Status(ref val)) => val.to_string(),
+            Ok(Value::Bulk(ref items)) => {
+                let vec: Vec<String> = FromRedisValue::from_redis_values(items)?;
+                vec.join(" ")
+            },
+            Err(err) => err.to_string()
+        };
+
+        info!(%resp, "The server answered");
+    }
+
+
+    Ok(())
+}
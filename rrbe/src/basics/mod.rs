@@ -0,0 +1,5 @@
+//! First steps with redis-rs
+
+pub mod address_port;
+pub mod getting_started;
+pub mod low_level_api;
@@ -0,0 +1,7 @@
+//! The redis-rs by example programs as library functions.
+//! Each example is a module with a `run` function that takes the resolved connection `Config`,
+//! and an `ABOUT` text for its command line.  The standalone example binaries and the `rrbe`
+//! command both call them.
+
+pub mod basics;
+pub mod streams;
@@ -0,0 +1,328 @@
+use std::env;
+use std::future::Future;
+use std::path::Path;
+use std::process::ExitCode;
+
+use clap_v3::{App, AppSettings, Arg, ArgMatches};
+use rrbe::basics::{address_port, getting_started, low_level_api};
+use rrbe::streams::*;
+use rs_util::export::Format;
+use rs_util::{retention_args, time_range_args, Config, ConfigError, Retention, StreamId, TimeRange};
+
+const ABOUT: &str = "
+    redis-rs by example
+            Every example in one command.  The connection options go before the example, e.g.
+            rrbe --host 10.10.1.50 --port 7000 streams producer-1";
+
+/// The subcommand for an example, listed with a one line summary
+fn example(name: &'static str, summary: &'static str, about: &'static str) -> App<'static> {
+    App::new(name).about(summary).long_about(about)
+}
+
+/// The arguments of export and import, which read and write the same formats
+fn transfer_args(app: App<'static>) -> App<'static> {
+    app.arg(Arg::with_name("STREAM").help("Stream name, e.g. numbers").required(true))
+        .arg(
+            Arg::with_name("format")
+                .long("format")
+                .help("File format")
+                .possible_values(&["ndjson", "csv"])
+                .default_value("ndjson"),
+        )
+        .arg(
+            Arg::with_name("from")
+                .long("from")
+                .help("Start at this entry ID, e.g. to resume where a stopped run left off")
+                .value_name("ID")
+                .takes_value(true),
+        )
+}
+
+/// The positional arguments of consumer-group-consumer and its async twin
+fn consumer_args(app: App<'static>) -> App<'static> {
+    app.arg(Arg::with_name("STREAM").help("Stream name").required(true))
+        .arg(Arg::with_name("GROUP").help("Consumer group name").required(true))
+        .arg(Arg::with_name("CONSUMER").help("Consumer instance name").required(true))
+}
+
+fn app() -> App<'static> {
+    rs_util::app("rrbe", ABOUT)
+        .setting(AppSettings::SubcommandRequiredElseHelp)
+        .subcommand(
+            App::new("basics")
+                .about("First steps with redis-rs")
+                .setting(AppSettings::SubcommandRequiredElseHelp)
+                .subcommand(example("getting-started", "Connect to a local server and set, get and delete a key", getting_started::ABOUT))
+                .subcommand(example("address-port", "Connect to the given host, port and DB", address_port::ABOUT))
+                .subcommand(
+                    example("low-level-api", "Run a command with the low-level API", low_level_api::ABOUT)
+                        .arg(Arg::with_name("COMMAND").help("Redis command to be executed").multiple(true)),
+                ),
+        )
+        .subcommand(
+            App::new("streams")
+                .about("Redis University 202 - Streams")
+                .setting(AppSettings::SubcommandRequiredElseHelp)
+                .subcommand(example("hello-world", "Print the value of the key hello", helloworld::ABOUT))
+                .subcommand(
+                    example("test-connection", "Diagnose the connection to the server", test_connection::ABOUT)
+                        .arg(Arg::with_name("json").long("json").help("Print the report as JSON")),
+                )
+                .subcommand(retention_args(example("producer-1", "Write the natural numbers to the stream numbers, forever", producer_1::ABOUT)))
+                .subcommand(retention_args(example("producer-2", "Write the numbers 0 to 100 to the stream numbers", producer_2::ABOUT)))
+                .subcommand(time_range_args(example("range-1", "Sum the numbers in the stream numbers with XRANGE", range_1::ABOUT)))
+                .subcommand(retention_args(example("intro-producer", "Intro Lab: simulate temperature sensors", intro_producer::ABOUT)))
+                .subcommand(retention_args(example(
+                    "intro-producer-async",
+                    "Intro Lab: simulate temperature sensors (async)",
+                    intro_producer_async::ABOUT,
+                )))
+                .subcommand(example("intro-consumer", "Intro Lab: write the readings to a data warehouse", intro_consumer::ABOUT))
+                .subcommand(example("intro-consumer-async", "Intro Lab: write the readings to a data warehouse (async)", intro_consumer_async::ABOUT))
+                .subcommand(example("intro-consumer-average", "Intro Lab: print the rolling average temperature", intro_consumer_average::ABOUT))
+                .subcommand(
+                    retention_args(example("consumer-group", "Consumer Group Lab: a producer, ten consumers and chaos", consumer_group::ABOUT)).arg(
+                        Arg::with_name("chaos")
+                            .long("chaos")
+                            .help("What happens to the consumers the chaos stops")
+                            .possible_values(&["restart", "remove"])
+                            .default_value("restart"),
+                    ),
+                )
+                .subcommand(consumer_args(example("consumer-group-consumer", "Consumer Group Lab: a single consumer", consumer_group_consumer::ABOUT)))
+                .subcommand(consumer_args(
+                    example("consumer-group-consumer-async", "Consumer Group Lab: a single consumer (async)", consumer_group_consumer_async::ABOUT),
+                ))
+                .subcommand(
+                    example("inspect", "Show a stream, its groups, consumers and pending entries", inspect::ABOUT)
+                        .arg(Arg::with_name("STREAM").help("Stream name, e.g. numbers").required(true))
+                        .arg(Arg::with_name("json").long("json").help("Print the inspection as JSON"))
+                        .arg(
+                            Arg::with_name("watch")
+                                .long("watch")
+                                .help("Inspect again every INTERVAL, e.g. 2s, until Ctrl-C")
+                                .value_name("INTERVAL")
+                                .takes_value(true),
+                        )
+                        .arg(
+                            Arg::with_name("oldest")
+                                .long("oldest")
+                                .help("How many of the oldest pending entries to show per group")
+                                .takes_value(true)
+                                .default_value("10"),
+                        ),
+                )
+                .subcommand(
+                    time_range_args(transfer_args(example("export", "Write the entries of a stream to an NDJSON or CSV file", export::ABOUT)))
+                        .arg(Arg::with_name("output").long("output").help("File to write, instead of stdout").value_name("FILE").takes_value(true))
+                        .arg(
+                            Arg::with_name("count")
+                                .long("count")
+                                .help("Entries per XRANGE")
+                                .takes_value(true)
+                                .default_value("1000"),
+                        ),
+                )
+                .subcommand(
+                    transfer_args(example("import", "Add the entries of an NDJSON or CSV file to a stream", import::ABOUT))
+                        .arg(Arg::with_name("input").long("input").help("File to read, instead of stdin").value_name("FILE").takes_value(true))
+                        .arg(Arg::with_name("new-ids").long("new-ids").help("Let the server generate new IDs instead of keeping the original ones"))
+                        .arg(
+                            Arg::with_name("batch")
+                                .long("batch")
+                                .help("XADDs per pipeline")
+                                .takes_value(true)
+                                .default_value("500"),
+                        ),
+                )
+                .subcommand(
+                    retention_args(example("replay", "Replay a stream into another one at its original pace", replay::ABOUT))
+                        .arg(Arg::with_name("SOURCE").help("Stream to replay, e.g. stream:weather").required(true))
+                        .arg(Arg::with_name("DESTINATION").help("Stream to add the entries to").required(true))
+                        .arg(
+                            Arg::with_name("speed")
+                                .long("speed")
+                                .help("How much faster than the original, from 0.001x to 1000x, e.g. 10x or 0.5x")
+                                .takes_value(true)
+                                .default_value("1x"),
+                        )
+                        .arg(Arg::with_name("loop").long("loop").help("Start over at the end of the source, until Ctrl-C"))
+                        .arg(
+                            Arg::with_name("rewrite-time")
+                                .long("rewrite-time")
+                                .help("Move the timestamps in FIELD, in seconds or milliseconds, to the time of the replay")
+                                .value_name("FIELD")
+                                .takes_value(true)
+                                .multiple(true)
+                                .number_of_values(1),
+                        ),
+                )
+                .subcommand(
+                    example("dlq", "List or replay the entries consumers moved to a dead-letter stream", dlq::ABOUT)
+                        .arg(Arg::with_name("STREAM").help("Stream name, e.g. numbers").required(true))
+                        .arg(Arg::with_name("ID").help("Dead letter IDs [default: all of them]").multiple(true))
+                        .arg(
+                            Arg::with_name("count")
+                                .long("count")
+                                .help("How many dead letters to look at, oldest first")
+                                .takes_value(true)
+                                .default_value("100"),
+                        )
+                        .arg(Arg::with_name("replay").long("replay").help("Add the entries back to the stream")),
+                ),
+        )
+}
+
+/// Run an async example on a new runtime
+fn block_on<F>(future: F) -> rs_util::Result<()>
+where
+    F: Future<Output = rs_util::Result<()>>,
+{
+    tokio::runtime::Runtime::new()?.block_on(future)
+}
+
+/// The name and matches of the chosen subcommand
+fn subcommand(matches: &ArgMatches) -> (&str, &ArgMatches) {
+    let name = matches.subcommand_name().expect("[ERROR] A subcommand is required!");
+    (name, matches.subcommand_matches(name).unwrap())
+}
+
+/// The chaos mode chosen with --chaos
+fn chaos(matches: &ArgMatches) -> consumer_group::Chaos {
+    match matches.value_of("chaos") {
+        Some("remove") => consumer_group::Chaos::Remove,
+        _ => consumer_group::Chaos::Restart,
+    }
+}
+
+/// The report format chosen with --json
+fn output(matches: &ArgMatches) -> test_connection::Output {
+    if matches.is_present("json") {
+        test_connection::Output::Json
+    } else {
+        test_connection::Output::Text
+    }
+}
+
+/// A numeric option with a default value
+fn number(matches: &ArgMatches, name: &str) -> Result<usize, ConfigError> {
+    let value = matches.value_of(name).unwrap();
+    value.parse().map_err(|_| ConfigError::from(format!("--{}: '{}' is not a number", name, value)))
+}
+
+/// A numeric option with a default value, which must be at least 1, e.g. a page or batch size
+fn positive(matches: &ArgMatches, name: &str) -> Result<usize, ConfigError> {
+    match number(matches, name)? {
+        0 => Err(ConfigError::from(format!("--{}: must be at least 1", name))),
+        n => Ok(n),
+    }
+}
+
+/// The format and the first ID of export and import
+fn transfer(matches: &ArgMatches) -> Result<(Format, StreamId), ConfigError> {
+    let format = Format::from_name(matches.value_of("format").unwrap()).unwrap_or(Format::Ndjson);
+    let from = match matches.value_of("from") {
+        Some(id) => id.parse().map_err(|e| ConfigError::from(format!("--from: {}", e)))?,
+        None => StreamId::MIN,
+    };
+    Ok((format, from))
+}
+
+fn basics(config: &Config, matches: &ArgMatches) -> rs_util::Result<()> {
+    match subcommand(matches) {
+        ("getting-started", _) => getting_started::run(),
+        ("address-port", _) => address_port::run(config),
+        ("low-level-api", sub) => {
+            let user_cmd: Vec<&str> = sub.values_of("COMMAND").map(|vals| vals.collect()).unwrap_or_default();
+            low_level_api::run(config, &user_cmd)
+        }
+        (name, _) => unreachable!("unknown basics example {}", name),
+    }
+}
+
+fn streams(config: &Config, matches: &ArgMatches) -> rs_util::Result<()> {
+    let (name, sub) = subcommand(matches);
+    let consumer = || (sub.value_of("STREAM").unwrap(), sub.value_of("GROUP").unwrap(), sub.value_of("CONSUMER").unwrap());
+    let retention = || Retention::from_matches(sub);
+
+    match name {
+        "hello-world" => helloworld::run(config),
+        "test-connection" => test_connection::run(config, output(sub)),
+        "producer-1" => producer_1::run(config, retention()?),
+        "producer-2" => producer_2::run(config, retention()?),
+        "range-1" => range_1::run(config, TimeRange::from_matches(sub)?),
+        "intro-producer" => intro_producer::run(config, retention()?),
+        "intro-producer-async" => block_on(intro_producer_async::run(config, retention()?)),
+        "intro-consumer" => intro_consumer::run(config),
+        "intro-consumer-async" => block_on(intro_consumer_async::run(config)),
+        "intro-consumer-average" => intro_consumer_average::run(config),
+        "consumer-group" => {
+            // The consumers are this same binary, running the consumer-group-consumer example
+            let command = consumer_group::ConsumerCommand {
+                program: env::current_exe()?,
+                args: vec!["streams".to_string(), "consumer-group-consumer".to_string()],
+            };
+            consumer_group::run(config, &command, chaos(sub), retention()?)
+        }
+        "consumer-group-consumer" => {
+            let (stream, group, consumer) = consumer();
+            consumer_group_consumer::run(config, stream, group, consumer)
+        }
+        "consumer-group-consumer-async" => {
+            let (stream, group, consumer) = consumer();
+            block_on(consumer_group_consumer_async::run(config, stream, group, consumer))
+        }
+        "inspect" => {
+            let output = if sub.is_present("json") { inspect::Output::Json } else { inspect::Output::Tables };
+            let watch = match sub.value_of("watch") {
+                Some(every) => Some(
+                    rs_util::parse_duration(every)
+                        .ok_or_else(|| ConfigError::from(format!("--watch: '{}' is not a duration", every)))?,
+                ),
+                None => None,
+            };
+            inspect::run(config, sub.value_of("STREAM").unwrap(), output, watch, number(sub, "oldest")?)
+        }
+        "export" => {
+            let (format, from) = transfer(sub)?;
+            let output = sub.value_of("output").map(Path::new);
+            let range = TimeRange::from_matches(sub)?;
+            export::run(config, sub.value_of("STREAM").unwrap(), format, output, range, from, positive(sub, "count")?)
+        }
+        "import" => {
+            let (format, from) = transfer(sub)?;
+            let input = sub.value_of("input").map(Path::new);
+            let ids = if sub.is_present("new-ids") { import::Ids::New } else { import::Ids::Original };
+            import::run(config, sub.value_of("STREAM").unwrap(), format, input, from, ids, positive(sub, "batch")?)
+        }
+        "replay" => {
+            let speed = sub.value_of("speed").unwrap();
+            let options = replay::Options {
+                speed: rs_util::parse_speed(speed).ok_or_else(|| ConfigError::from(format!("--speed: '{}' is not a speed from 0.001x to 1000x, e.g. 10x", speed)))?,
+                repeat: sub.is_present("loop"),
+                rewrite_time: sub.values_of("rewrite-time").map(|fields| fields.map(String::from).collect()).unwrap_or_default(),
+            };
+            replay::run(config, sub.value_of("SOURCE").unwrap(), sub.value_of("DESTINATION").unwrap(), &options, retention()?)
+        }
+        "dlq" => {
+            let count = positive(sub, "count")?;
+            let action = if sub.is_present("replay") { dlq::Action::Replay } else { dlq::Action::List };
+            let ids: Vec<&str> = sub.values_of("ID").map(|vals| vals.collect()).unwrap_or_default();
+            dlq::run(config, sub.value_of("STREAM").unwrap(), count, action, &ids)
+        }
+        name => unreachable!("unknown streams example {}", name),
+    }
+}
+
+fn main() -> ExitCode {
+    let matches = app().get_matches();
+    // The connection options are parsed once, for every example
+    let config = rs_util::config_or_exit(&matches);
+
+    let result = match subcommand(&matches) {
+        ("basics", sub) => basics(&config, sub),
+        ("streams", sub) => streams(&config, sub),
+        (name, _) => unreachable!("unknown example group {}", name),
+    };
+    rs_util::exit_code(result)
+}
@@ -0,0 +1,314 @@
+use std::io;
+use std::path::PathBuf;
+use std::process::{Child, Command};
+use std::sync::mpsc::{self, TryRecvError};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+use rand::prelude::*;
+use redis::Commands;
+use rs_util::{Config, Context, Error, Feature, Retention};
+
+use super::NUMBERS;
+use tracing::{info, info_span, warn};
+
+pub const ABOUT: &str = "
+    Redis University 202 - Streams: Consumer Group Demo
+            A multi-threaded application that demonstrates how multiple members of the
+            same consumer group work together to process a stream. It also demonstrates
+            how individual consumers can recover from complete failures without
+            catastrophic effects.
+            With --chaos remove, stopped consumers never come back, and the others
+            claim the entries they left pending.
+            Entries that can't be processed are moved to {numbers}:dlq, which
+            is kept on exit: see rrbe streams dlq.
+            With --maxlen, --min-id or --max-age, the producer trims the stream, and the
+            consumers acknowledge the pending entries that were trimmed away.
+            Press ENTER, Ctrl-C or send SIGTERM to stop; the consumers finish their current entry.";
+
+const GROUP: &str = "primes";
+const MEMBERS: i16 = 10;
+/// How long a consumer gets to finish its entry after SIGTERM, before it is killed
+const CONSUMER_GRACE: Duration = Duration::from_secs(10);
+/// How many consumers the chaos leaves running, when it removes them
+const SURVIVORS: usize = 2;
+
+/// What the chaos does with the consumer it stopped
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Chaos {
+    /// Start it again under the same name, so it recovers its own pending entries
+    Restart,
+    /// Leave it out for good, so its pending entries have to be claimed by the others
+    Remove,
+}
+
+/// How to start a consumer process.
+/// The consumer's connection arguments come first, then `args`, then the stream, group and consumer names.
+#[derive(Clone, Debug)]
+pub struct ConsumerCommand {
+    pub program: PathBuf,
+    pub args: Vec<String>,
+}
+
+impl ConsumerCommand {
+    /// The consumer_group_consumer binary next to consumer_group_main, as laid out by consumer_group.sh
+    pub fn standalone() -> ConsumerCommand {
+        ConsumerCommand { program: PathBuf::from("./consumer_group_consumer"), args: vec![] }
+    }
+}
+
+/// Initialize the Stream and the primes consumer group
+fn setup(pool: &rs_util::Pool) -> rs_util::Result<()> {
+    // Borrow a connection to the Redis server from the pool
+    let mut con = pool.get()?;
+
+    // Make sure the stream does not already exist
+    let _: () = con
+        .del(NUMBERS)
+        .with_context(|| format!("deleting the stream {}", NUMBERS))?;
+    // Create the stream and the consumer group
+    let _: () = con
+        .xgroup_create_mkstream(NUMBERS, GROUP, 0)
+        .with_context(|| format!("creating the group {} on stream {}", GROUP, NUMBERS))?;
+    Ok(())
+}
+
+/// Produce a stream of natural numbers, trimming it as `retention` says
+fn producer(pool: rs_util::Pool, mut retention: Retention, rx: mpsc::Receiver<&str>) -> rs_util::Result<()> {
+    let _producer = info_span!("producer", stream = NUMBERS).entered();
+    // rs_util names every connection (CLIENT SETNAME), so the producer shows up in CLIENT LIST.
+    // The producer keeps its connection until it stops, then it goes back to the pool.
+    let mut con = pool.get()?;
+    let mut n = 0;
+    let mut rng = thread_rng();
+    loop {
+        // Check if the stop signal has been received
+        match rx.try_recv() {
+            Ok(val) => {
+                if val == "STOP" {
+                    info!("Stop signal received: {}.", val);
+                    break;
+                }
+            }
+            Err(TryRecvError::Disconnected) => {
+                info!("Channel disconnected. Stopping producer thread.");
+                break;
+            }
+            Err(TryRecvError::Empty) => {}
+        }
+        // Write data to stream
+        let id: String = retention
+            .xadd(NUMBERS, &[("n".to_string(), n.to_string())])
+            .query(&mut *con)
+            .with_context(|| format!("writing number {} to stream {}", n, NUMBERS))?;
+        tracing::debug!(n, %id, "Produced");
+        rs_util::metrics().produced(NUMBERS);
+        if let Some(trim) = retention.trim_due(NUMBERS) {
+            let trimmed: usize = trim.query(&mut *con).context("trimming the stream")?;
+            info!(trimmed, "Trimmed the stream");
+        }
+        // Pause for a random amount of time
+        n += 1;
+        let sleep_time: u64 = (rng.gen_range(1000..=2000) as f64 / (MEMBERS as f64)).floor() as u64;
+        thread::sleep(Duration::from_millis(sleep_time));
+    }
+    Ok(())
+}
+
+/// A Consumer has a name and the process ID of its spawned process.
+struct Consumer {
+    name: String,
+    process_id: Child,
+}
+
+/// Create a vector of Consumers containing MEMBERS Consumers
+/// Use the new_consumer function to produce each consumer
+fn consumers(config: &Config, command: &ConsumerCommand) -> rs_util::Result<Vec<Consumer>> {
+    let mut consumers: Vec<Consumer> = vec![];
+    for i in 1..=MEMBERS {
+        let name = format!("BOB-{:02}", i);
+        consumers.push(new_consumer(name, config, command)?);
+    }
+    Ok(consumers)
+}
+
+/// Start a new Consumer process
+/// The consumer connects to the same server as this process.
+fn new_consumer(name: String, config: &Config, command: &ConsumerCommand) -> rs_util::Result<Consumer> {
+    let mut consumer = Command::new(&command.program);
+    // A Ctrl-C in the terminal goes to the whole process group.  The consumers get a group of
+    // their own, so that they only hear about it from this process, once the producer has stopped.
+    #[cfg(unix)]
+    std::os::unix::process::CommandExt::process_group(&mut consumer, 0);
+    let process_id = consumer
+        .args(config.to_args())
+        // The credentials go through the environment, where other users can't read them
+        .envs(config.to_env())
+        // The consumers log the same way as this process
+        .args(rs_util::log_config().to_args())
+        // Each consumer's connection is named after it in CLIENT LIST
+        .args(["--client-name", &format!("{}-{}", GROUP, name)])
+        .args(&command.args)
+        .args([NUMBERS, GROUP, &name])
+        .spawn()
+        .with_context(|| format!("starting consumer {} ({})", name, command.program.display()))?;
+    Ok(Consumer { name, process_id })
+}
+
+/// Randomly choose to stop a consumer
+/// If on a loop, it is decided to stop a consumer, choose which consumer randomly.
+/// Then restart the same consumer using the new_consumer function, or with Chaos::Remove,
+/// leave it out until only SURVIVORS consumers are left.
+/// Wait for a 1-2 seconds between each loop.
+/// If a consumer can't be restarted, the chaos stops and the consumer is left out.
+fn chaos(
+    mut consumers: Vec<Consumer>,
+    mode: Chaos,
+    config: Config,
+    command: ConsumerCommand,
+    rx: mpsc::Receiver<&str>,
+) -> Vec<Consumer> {
+    let _chaos = info_span!("chaos").entered();
+    loop {
+        // Check if the stop signal has been received
+        match rx.try_recv() {
+            Ok(val) => {
+                if val == "STOP" {
+                    info!("Stop signal received: {}.", val);
+                    break;
+                }
+            }
+            Err(TryRecvError::Disconnected) => {
+                info!("Channel disconnected. Stopping chaos thread.");
+                break;
+            }
+            Err(TryRecvError::Empty) => {}
+        }
+        let mut rng = thread_rng();
+        if rng.gen_range(2..=12) == 2 && (mode == Chaos::Restart || consumers.len() > SURVIVORS) {
+            let victim = rng.gen_range(0..consumers.len());
+            let name = consumers[victim].name.clone();
+            if let Err(e) = consumers[victim].process_id.kill() {
+                warn!(consumer = %name, error = %e, "Could not stop the consumer");
+            }
+            match mode {
+                Chaos::Restart => match new_consumer(name, &config, &command) {
+                    Ok(consumer) => {
+                        info!(consumer = %consumer.name, "Restarted");
+                        consumers[victim] = consumer;
+                    }
+                    Err(e) => {
+                        warn!(error = %e, "Stopping the chaos");
+                        consumers.remove(victim);
+                        break;
+                    }
+                },
+                Chaos::Remove => {
+                    let _ = consumers[victim].process_id.wait();
+                    consumers.remove(victim);
+                    info!(consumer = %name, remaining = consumers.len(), "Removed for good");
+                }
+            }
+        }
+        thread::sleep(Duration::from_millis(rng.gen_range(1000..=2000)));
+    }
+
+    consumers
+}
+
+/// Cleanup the application gracefully on exit.
+/// 1. Stop the producer thread
+/// 2. Stop the chaos thread
+/// 3. Stop the consumers with SIGTERM, so they acknowledge the entry at hand
+/// 4. Delete the stream from Redis
+///
+/// A producer that stopped early because of an error is reported once everything is cleaned up.
+fn cleanup(
+    prod_tx: mpsc::Sender<&str>,
+    prod_handle: JoinHandle<rs_util::Result<()>>,
+    chaos_tx: mpsc::Sender<&str>,
+    chaos_handle: JoinHandle<Vec<Consumer>>,
+    pool: rs_util::Pool,
+) -> rs_util::Result<()> {
+    info!("Cleaning up and exiting...");
+    // 1. Stop the producer thread.  Sending fails if it has already stopped.
+    info!("Stopping producer thread...");
+    let _ = prod_tx.send("STOP");
+    let produced = prod_handle
+        .join()
+        .unwrap_or_else(|_| Err(Error::Other("The producer thread panicked".to_string())));
+
+    // 2. Stop the chaos thread
+    info!("Stopping the chaos thread...");
+    let _ = chaos_tx.send("STOP");
+    let consumers = chaos_handle.join().unwrap_or_default();
+
+    // 3. Stop the consumers, all at the same time
+    info!("Stopping consumer processes...");
+    let stopping: Vec<_> = consumers
+        .into_iter()
+        .map(|mut consumer| thread::spawn(move || (rs_util::terminate(&mut consumer.process_id, CONSUMER_GRACE), consumer.name)))
+        .collect();
+    for handle in stopping {
+        if let Ok((Err(e), name)) = handle.join() {
+            warn!(consumer = %name, error = %e, "Could not stop the consumer");
+        }
+    }
+
+    // 4. Delete the stream key from Redis
+    let mut con = pool.get()?;
+    let _: i32 = con.del(NUMBERS).context("deleting the stream key")?;
+    produced
+}
+
+/// Run the lab, starting the consumers with `command`
+pub fn run(config: &Config, command: &ConsumerCommand, chaos_mode: Chaos, retention: Retention) -> rs_util::Result<()> {
+    println!("Press ENTER to run the application now.");
+    println!("Press ENTER again later, or Ctrl-C, to exit cleanly...");
+
+    // Wait for the user to press <ENTER> to start
+    let mut input = String::new();
+    io::stdin().read_line(&mut input)?;
+
+    // Share a small pool of connections between the setup, the producer thread and the cleanup
+    let pool = rs_util::pool(config, 2)?;
+    // Consumer groups need Redis 5.0, and MINID, for --min-id and --max-age, Redis 6.2
+    rs_util::capabilities(&mut *pool.get()?)?.require(Feature::Streams)?;
+    retention.check(&mut *pool.get()?)?;
+
+    // Initialize the stream and group
+    setup(&pool)?;
+    // With --metrics-addr, this process reports the stream and the group's pending entries and lag.
+    // The consumers don't serve metrics of their own, since they would all need a port.
+    rs_util::metrics().watch(NUMBERS);
+
+    // Start the consumers in separate child processes
+    let consumers = consumers(config, command)?;
+
+    // Start the chaos function in a separate thread
+    let (chaos_tx, chaos_rx) = mpsc::channel::<&str>();
+    let config_chaos = config.clone();
+    let command_chaos = command.clone();
+    let chaos_handle = thread::spawn(move || chaos(consumers, chaos_mode, config_chaos, command_chaos, chaos_rx));
+
+    // Start the producer in its own thread
+    let (prod_tx, prod_rx) = mpsc::channel::<&str>();
+    let pool_prod = pool.clone();
+    let prod_handle = thread::spawn(move || producer(pool_prod, retention, prod_rx));
+
+    // Wait for ENTER, SIGINT or SIGTERM to trigger the cleanup
+    let shutdown = rs_util::shutdown_token();
+    let enter = shutdown.clone();
+    thread::spawn(move || {
+        let mut input = String::new();
+        let _ = io::stdin().read_line(&mut input);
+        enter.trigger();
+    });
+    shutdown.wait();
+
+    // Clean up
+    cleanup(prod_tx, prod_handle, chaos_tx, chaos_handle, pool)?;
+    info!("Good-bye!");
+
+    Ok(())
+}
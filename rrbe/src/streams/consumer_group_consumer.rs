@@ -0,0 +1,68 @@
+use std::time::Duration;
+
+use rand::prelude::*;
+use redis::streams::StreamId;
+use is_prime::*;
+use rs_util::streams::{Ack, GroupWorker, IdlePolicy};
+use rs_util::{Config, Shutdown};
+use tracing::info;
+
+pub const ABOUT: &str = "
+    Redis University 202 - Streams: Consumer Group Demo
+            A single member of the primes consumer group, started by consumer_group_main.";
+
+/// Run the consumer named `consumer_name` of `group_name` on `stream_name` until it runs out of work
+/// or the process receives SIGINT or SIGTERM
+pub fn run(config: &Config, stream_name: &str, group_name: &str, consumer_name: &str) -> rs_util::Result<()> {
+    // With --metrics-addr, report the stream's length and the group's pending entries and lag
+    rs_util::metrics().watch(stream_name);
+
+    // Open connection to the redis server or cluster
+    let mut con = rs_util::connect(config)?;
+
+    // Create the consumer
+    let shutdown = rs_util::shutdown_token();
+    let stats = worker(stream_name, group_name, consumer_name, &shutdown).run(&mut con, &mut |entry: &StreamId| {
+        check_prime(entry, &shutdown)
+    })?;
+    info!(recovered = stats.recovered, claimed = stats.claimed, dead_lettered = stats.dead_lettered, handled = stats.handled, acked = stats.acked, "bye bye...");
+    Ok(())
+}
+
+/// A consumer for the given stream and group
+/// The consumer begins by determining if there are any pending items and processes them first.
+/// Once any pending items are processed, the consumer begins processing any new messages.
+/// If there are new new items on the stream for 100ms, the consumer releases its connection
+/// and tries again five more times, doubling the timeout time each time.  If no new data
+/// is available on the stream for 6.3 seconds, the consumer stops itself entirely.
+/// Each time the consumer reads from the stream, it may read a number of entries between 1 and 5,
+/// chosen at random when it starts.
+/// On shutdown the consumer stops after acknowledging the entry at hand.  The rest of the entries
+/// it has read stay pending, and are recovered the next time it starts.
+/// Every few seconds, the consumer also claims the entries left pending by consumers that
+/// stopped and never came back.  A batch takes up to 10 seconds to process, so an entry is
+/// only claimed after 15 seconds.
+/// An entry that is not a number, or that was delivered three times already without being
+/// acknowledged, is moved to the stream's dead-letter stream, see `rrbe streams dlq`.
+fn worker(stream_name: &str, group_name: &str, consumer_name: &str, shutdown: &Shutdown) -> GroupWorker {
+    GroupWorker::new(stream_name, group_name, consumer_name)
+        .batch_size(thread_rng().gen_range(1..6))
+        .block(Duration::from_millis(100))
+        .idle(IdlePolicy::Backoff { retries: 5 })
+        .claim_idle(Duration::from_secs(15), Duration::from_secs(5))
+        .dead_letter(3)
+        .shutdown(shutdown.clone())
+}
+
+/// Message processing consists of determining if the whole number read from the stream is a
+/// prime number or not and printing the result.  The worker then acknowledges the item to redis.
+fn check_prime(entry: &StreamId, shutdown: &Shutdown) -> rs_util::Result<Ack> {
+    let n: i32 = rs_util::entry_field(entry, "n")?;
+    //                   ^-We know that "n" is the name of the field in the stream item.
+    info!(n, prime = is_prime(&n.to_string()), "Processed");
+
+    // Add artificial time delay to allow for the chaos function to stop a process
+    // before it is able to complete processing entries.
+    shutdown.sleep(Duration::from_millis(thread_rng().gen_range(1000..=2000)));
+    Ok(Ack::Ack)
+}
@@ -0,0 +1,172 @@
+use std::time::{Duration, Instant};
+
+use rand::prelude::*;
+use redis::aio::MultiplexedConnection;
+use redis::streams::{StreamId, StreamPendingCountReply, StreamReadOptions, StreamReadReply};
+use redis::{AsyncCommands, RedisResult};
+use is_prime::*;
+use rs_util::dlq::DeadLetter;
+use rs_util::{Config, Context, EntryId};
+use tokio::time::sleep;
+use tracing::{info, info_span, warn, Instrument};
+
+pub const ABOUT: &str = "
+    Redis University 202 - Streams: Consumer Group Demo
+            A single member of the primes consumer group, using async redis-rs.
+            It takes the same arguments as consumer_group_consumer.";
+
+/// Run the consumer named `consumer_name` of `group_name` on `stream_name` until it runs out of work
+/// or the process receives a shutdown signal
+pub async fn run(config: &Config, stream_name: &str, group_name: &str, consumer_name: &str) -> rs_util::Result<()> {
+    // With --metrics-addr, report the stream's length and the group's pending entries and lag
+    rs_util::metrics().watch(stream_name);
+
+    // Every event of this process carries the consumer's stream, group and name, across the awaits
+    let span = info_span!("consumer", stream = stream_name, group = group_name, name = consumer_name);
+    async {
+        // Open connection to redis server
+        let mut con = rs_util::get_async_connection(config).await?;
+
+        // Run the consumer until it runs out of work or a shutdown signal arrives
+        tokio::select! {
+            result = consumer(&mut con, stream_name, group_name, consumer_name) => result?,
+            _ = rs_util::aio::shutdown_signal() => info!("Shutdown signal received - bye bye..."),
+        }
+        Ok(())
+    }
+    .instrument(span)
+    .await
+}
+
+/// Start a consumer for the given stream and group
+/// This is the async version of consumer_group_consumer's consumer and behaves the same way:
+/// pending items are processed first, then new ones, with doubling timeouts when the stream is idle.
+/// The consumer is cancelled between awaits when the process receives a shutdown signal.
+/// An entry is only acknowledged once it has been processed, so an entry that was interrupted
+/// stays pending and is recovered the next time this consumer starts.
+/// An entry that is not a number is moved to the stream's dead-letter stream and acknowledged,
+/// and so is, without being moved, a pending entry that was trimmed from the stream.
+async fn consumer(con: &mut MultiplexedConnection, stream_name: &str, group_name: &str, consumer_name: &str) -> rs_util::Result<()> {
+    let mut timeout = 100;
+    let mut retries = 0;
+    let mut recovery = true;
+    let mut from_id = EntryId::ZERO;
+
+    loop {
+        // Each time a consumer reads from the stream, it may read a random number of entries
+        // between 1 and 6.
+        let count = thread_rng().gen_range(1..6);
+        let opts = StreamReadOptions::default()
+            .group(group_name, consumer_name)
+            .count(count)
+            .block(timeout);
+        // Using the ID 0 asks for any pending messages from the stream
+        let reply: StreamReadReply = con
+            .xread_options(&[&stream_name], &[&from_id], &opts)
+            .await
+            .with_context(|| format!("{} - reading from stream {}", consumer_name, stream_name))?;
+
+        // Handle timeouts - when stream entries are not available to be read
+        if reply.keys.is_empty() {
+            if retries == 5 {
+                info!("Waited long enough - bye bye...");
+                break;
+            }
+            retries += 1;
+            timeout *= 2;
+            continue;
+        }
+
+        // If we have recovered from a timeout situation, reset the timeout thresholds
+        timeout = 100;
+        retries = 0;
+
+        if recovery {
+            // If the response is empty, then there are no pending messages.
+            if !reply.keys[0].ids.is_empty() {
+                info!("Recovering pending messages...");
+            } else {
+                // If there are no messages to recover, switch to fetching new messages.
+                info!("Processing new messages...");
+                recovery = false;
+                // Setting from_id to > tells redis to deliver the next undelivered item(s)
+                from_id = EntryId::Undelivered;
+                continue;
+            }
+        }
+
+        // Process messages.  The processing time of an entry runs from the read to its XACK.
+        let read_at = Instant::now();
+        for stream in &reply.keys {
+            rs_util::metrics().consumed(stream_name, group_name, stream.ids.len());
+            for id in &stream.ids {
+                // XADD needs at least one field: an entry without any was deleted from the stream,
+                // e.g. by trimming, while it was pending.  Nothing is left to process.
+                if id.map.is_empty() {
+                    warn!(id = %id.id, "The pending entry was deleted from the stream");
+                    let _: usize = con
+                        .xack(stream_name, group_name, &[&id.id])
+                        .await
+                        .with_context(|| format!("{} - acknowledging deleted entry {}", consumer_name, id.id))?;
+                    rs_util::metrics().deleted_pending(stream_name, group_name, 1);
+                    continue;
+                }
+                let n: i32 = match rs_util::entry_field(id, "n") {
+                    Ok(n) => n,
+                    Err(e) => {
+                        dead_letter(con, stream_name, group_name, consumer_name, id, &e.to_string())
+                            .instrument(info_span!("entry", id = %id.id))
+                            .await?;
+                        continue;
+                    }
+                };
+                async {
+                    info!(n, prime = is_prime(&n.to_string()), "Processed");
+                    let acked: RedisResult<usize> = con.xack(stream_name, group_name, &[&id.id]).await;
+                    if let Ok(acked) = acked {
+                        rs_util::metrics().acked(stream_name, group_name, acked);
+                        rs_util::metrics().observe_processing(stream_name, group_name, read_at.elapsed());
+                    }
+
+                    // Add artificial time delay to allow for the chaos function to stop a process
+                    // before it is able to complete processing entries.
+                    sleep(Duration::from_millis(thread_rng().gen_range(1000..=2000))).await;
+                }
+                .instrument(info_span!("entry", id = %id.id))
+                .await;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Move an entry that can't be processed to the dead-letter stream, then acknowledge it
+async fn dead_letter(
+    con: &mut MultiplexedConnection,
+    stream_name: &str,
+    group_name: &str,
+    consumer_name: &str,
+    entry: &StreamId,
+    error: &str,
+) -> rs_util::Result<()> {
+    warn!(error, "Moving the entry to the dead-letter stream");
+    // How many times the entry was delivered, for whoever looks at the dead letter
+    let pending: StreamPendingCountReply = con
+        .xpending_consumer_count(stream_name, group_name, &entry.id, &entry.id, 1, consumer_name)
+        .await
+        .with_context(|| format!("{} - counting the deliveries of entry {}", consumer_name, entry.id))?;
+    let deliveries = pending.ids.first().map_or(1, |pending| pending.times_delivered);
+
+    let letter = DeadLetter::new(stream_name, group_name, consumer_name, entry, deliveries, error);
+    let _: String = letter
+        .add_cmd()
+        .query_async(con)
+        .await
+        .with_context(|| format!("{} - moving entry {} to {}", consumer_name, entry.id, rs_util::dlq::key(stream_name)))?;
+    let _: usize = con
+        .xack(stream_name, group_name, &[&entry.id])
+        .await
+        .with_context(|| format!("{} - acknowledging dead letter {}", consumer_name, entry.id))?;
+    rs_util::metrics().dead_lettered(stream_name, group_name);
+    Ok(())
+}
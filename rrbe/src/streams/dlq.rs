@@ -0,0 +1,60 @@
+//! Look at the entries consumers moved to a dead-letter stream, and put them back
+
+use rs_util::dlq::{self, DeadLetter};
+use rs_util::{Config, Error};
+use tracing::info;
+
+pub const ABOUT: &str = "
+    Redis University 202 - Streams: Dead Letters
+            List the entries that the consumers of STREAM could not process, and moved
+            to its dead-letter stream, e.g. {numbers}:dlq for numbers.
+            With --replay, add them back to STREAM, where they are read like new entries,
+            and delete the dead letters.";
+
+/// What to do with the dead letters
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Action {
+    /// Print them
+    List,
+    /// Add them back to their stream
+    Replay,
+}
+
+/// List or replay the first `count` dead letters of `stream`, or only those with the given IDs
+pub fn run(config: &Config, stream: &str, count: usize, action: Action, ids: &[&str]) -> rs_util::Result<()> {
+    let mut con = rs_util::connect(config)?;
+
+    let letters = dlq::list(&mut con, stream, count)?;
+    let chosen: Vec<&DeadLetter> = letters.iter().filter(|letter| ids.is_empty() || ids.contains(&letter.id.as_str())).collect();
+    if let Some(missing) = ids.iter().find(|id| !chosen.iter().any(|letter| letter.id == **id)) {
+        return Err(Error::Other(format!("No dead letter {} among the first {} of {}", missing, count, dlq::key(stream))));
+    }
+
+    match action {
+        Action::List => {
+            println!("{}: {} dead letters", dlq::key(stream), chosen.len());
+            for letter in chosen {
+                print(letter);
+            }
+        }
+        Action::Replay => {
+            for letter in &chosen {
+                let id = dlq::replay(&mut con, letter)?;
+                info!(dead_letter = %letter.id, entry = %letter.entry_id, new_id = %id, stream = %letter.stream, "Replayed");
+            }
+            info!(count = chosen.len(), "Replayed the dead letters");
+        }
+    }
+    Ok(())
+}
+
+fn print(letter: &DeadLetter) {
+    println!(
+        "\n{}  entry {} of {}, group {}, consumer {}, {} deliveries, at {} ms",
+        letter.id, letter.entry_id, letter.stream, letter.group, letter.consumer, letter.deliveries, letter.at
+    );
+    println!("    error: {}", letter.error);
+    for (field, value) in &letter.fields {
+        println!("    {} = {}", field, String::from_utf8_lossy(value));
+    }
+}
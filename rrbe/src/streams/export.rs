@@ -0,0 +1,87 @@
+//! Write the entries of a stream to an NDJSON or CSV file, a page of XRANGE at a time
+
+use std::fs::OpenOptions;
+use std::io::{self, Write};
+use std::path::Path;
+
+use rs_util::export::{Format, Record, Writer};
+use rs_util::{Config, Context, RangePages, StreamId, TimeRange};
+use tracing::info;
+
+pub const ABOUT: &str = "
+    Redis University 202 - Streams: Export
+            Write the entries of STREAM to a file, or to stdout, as NDJSON or CSV, reading
+            them with XRANGE a page at a time.  --since and --until export the entries added
+            during a while only, e.g. --since 'yesterday 09:00' --until '(yesterday 10:00'.
+            An export that was stopped, by Ctrl-C or an
+            error, logs the ID to resume from: --from ID continues it, appending to the file.
+            The import example adds the entries to a stream again.";
+
+/// Export the entries of `stream` in `range`, from `from` on, to `output` or stdout, `count` per XRANGE
+pub fn run(
+    config: &Config,
+    stream: &str,
+    format: Format,
+    output: Option<&Path>,
+    range: TimeRange,
+    from: StreamId,
+    count: usize,
+) -> rs_util::Result<()> {
+    let mut con = rs_util::connect(config)?;
+    let shutdown = rs_util::shutdown_token();
+
+    let out: Box<dyn Write> = match output {
+        // Resuming adds to what the stopped export wrote
+        Some(path) => Box::new(
+            OpenOptions::new()
+                .write(true)
+                .create(true)
+                .append(from != StreamId::MIN)
+                .truncate(from == StreamId::MIN)
+                .open(path)
+                .with_context(|| format!("opening {}", path.display()))?,
+        ),
+        None => Box::new(io::stdout().lock()),
+    };
+    let mut writer = Writer::new(out, format);
+
+    let mut pages = RangePages::new(&mut con, stream, from.max(range.start()), range.end().into(), count);
+    let mut total = 0;
+    loop {
+        // A page is written whole or not at all, so a failed export resumes where the page starts
+        let start = pages.next_id();
+        let page = match pages.next() {
+            Some(page) => page,
+            None => break,
+        };
+        let result = page.and_then(|entries| {
+            let records = entries.iter().map(Record::from_entry).collect::<rs_util::Result<Vec<_>>>()?;
+            for record in &records {
+                writer.write(record)?;
+            }
+            writer.flush()?;
+            Ok(records.len())
+        });
+        match result {
+            Ok(n) => total += n,
+            Err(e) => return Err(e.context(format!("exporting {} ({})", stream, resume(start)))),
+        }
+        info!(stream = %stream, exported = total, "Exported a page");
+        if shutdown.is_triggered() {
+            info!(stream = %stream, exported = total, "Stopped, {}", resume(pages.next_id()));
+            return Ok(());
+        }
+    }
+
+    writer.into_inner()?;
+    info!(stream = %stream, exported = total, "Exported the stream");
+    Ok(())
+}
+
+/// How to carry on with a stopped export
+fn resume(next: Option<StreamId>) -> String {
+    match next {
+        Some(id) => format!("resume with --from {}", id),
+        None => "nothing is left to export".to_string(),
+    }
+}
@@ -0,0 +1,14 @@
+use redis::Commands;
+use rs_util::Config;
+
+pub const ABOUT: &str = "
+    Redis University 202 - Streams: Helloworld
+            A very simple app that assumes there is a key named hello already
+            in the redis DB.  It gets this value and displays its value on the screen.";
+
+pub fn run(config: &Config) -> rs_util::Result<()> {
+    let mut con = rs_util::get_connection(config)?;
+    let resp: String = con.get("hello")?;
+    println!("{}", resp);
+    Ok(())
+}
\ No newline at end of file
@@ -0,0 +1,81 @@
+//! Add the entries of an NDJSON or CSV file to a stream, in pipelined batches of XADD
+
+use std::fs::File;
+use std::io::{self, BufRead, BufReader};
+use std::path::Path;
+
+use rs_util::export::{self, Format, Record};
+use rs_util::{Config, Context, StreamId};
+use tracing::info;
+
+pub const ABOUT: &str = "
+    Redis University 202 - Streams: Import
+            Add the entries of a file written by the export example, or of stdin, to STREAM.
+            They keep their IDs, which must be higher than those already in STREAM, unless
+            --new-ids lets the server generate new ones.  The XADDs are sent in pipelined
+            batches.  --from ID skips the entries before ID in the file, to resume an import
+            that was stopped: a batch that failed may have been added in part.";
+
+/// Which IDs the imported entries get
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Ids {
+    /// The IDs in the file
+    Original,
+    /// `*`: the server generates new IDs
+    New,
+}
+
+/// Import the entries of `input`, or stdin, from `from` on into `stream`, `batch` XADDs per pipeline
+pub fn run(config: &Config, stream: &str, format: Format, input: Option<&Path>, from: StreamId, ids: Ids, batch: usize) -> rs_util::Result<()> {
+    let mut con = rs_util::connect(config)?;
+    let shutdown = rs_util::shutdown_token();
+
+    let input: Box<dyn BufRead> = match input {
+        Some(path) => Box::new(BufReader::new(File::open(path).with_context(|| format!("opening {}", path.display()))?)),
+        None => Box::new(io::stdin().lock()),
+    };
+
+    let mut records = export::read(input, format).peekable();
+    let (mut read, mut total, mut skipped) = (0, 0, 0);
+    while records.peek().is_some() {
+        let mut pipe = redis::pipe();
+        let (mut first, mut last, mut n) = (None, None, 0);
+        for record in records.by_ref().take(batch) {
+            read += 1;
+            let Record { id, fields } = record.with_context(|| format!("reading record {}", read))?;
+            let original: StreamId = id.parse().with_context(|| format!("record {}", read))?;
+            if original < from {
+                skipped += 1;
+                continue;
+            }
+            let fields: Vec<(String, String)> = fields.into_iter().collect();
+            match ids {
+                Ids::Original => pipe.xadd(stream, &id, &fields),
+                Ids::New => pipe.xadd(stream, "*", &fields),
+            }
+            .ignore();
+            first.get_or_insert(original);
+            last = Some(original);
+            n += 1;
+        }
+        let (Some(first), Some(last)) = (first, last) else {
+            continue;
+        };
+
+        pipe.query::<()>(&mut con)
+            .with_context(|| format!("importing the entries {} to {} into {} (resume with --from {})", first, last, stream, first))?;
+        total += n;
+        info!(stream = %stream, imported = total, skipped, last = %last, "Imported a batch");
+
+        if shutdown.is_triggered() {
+            match last.next() {
+                Some(next) => info!(stream = %stream, imported = total, "Stopped, resume with --from {}", next),
+                None => info!(stream = %stream, imported = total, "Stopped after the highest possible ID"),
+            }
+            return Ok(());
+        }
+    }
+
+    info!(stream = %stream, imported = total, skipped, "Imported the file");
+    Ok(())
+}
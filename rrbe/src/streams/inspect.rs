@@ -0,0 +1,316 @@
+//! Show what is going on in a stream and its consumer groups: XINFO STREAM, GROUPS and
+//! CONSUMERS, and the oldest pending entries of each group from XPENDING.
+
+use std::collections::HashMap;
+use std::io;
+use std::time::Duration;
+
+use colored::*;
+use redis::streams::{StreamPendingCountReply, StreamPendingReply};
+use redis::{Commands, ConnectionLike, FromRedisValue, Value};
+use rs_util::{Config, Context, Feature};
+use serde::Serialize;
+
+pub const ABOUT: &str = "
+    Redis University 202 - Streams: Stream Inspector
+            Show a stream as tables: its length, first and last entries and radix tree,
+            the last delivered ID, pending count and lag of each consumer group, the
+            pending count and idle time of each consumer, and the oldest pending entries.
+            --watch refreshes the tables until Ctrl-C; --json prints the same as JSON,
+            one object per line with --watch.";
+
+/// How the inspection is printed
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Output {
+    /// Tables, for people
+    Tables,
+    /// A JSON object, for scripts
+    Json,
+}
+
+/// XINFO STREAM, and the groups
+#[derive(Debug, Default, Serialize)]
+pub struct Inspection {
+    pub stream: String,
+    pub length: i64,
+    pub radix_tree_keys: i64,
+    pub radix_tree_nodes: i64,
+    pub last_generated_id: String,
+    pub first_entry: Option<String>,
+    pub last_entry: Option<String>,
+    /// Redis 7.0+
+    pub entries_added: Option<i64>,
+    pub groups: Vec<Group>,
+}
+
+/// XINFO GROUPS, the consumers of the group and its pending entries
+#[derive(Debug, Default, Serialize)]
+pub struct Group {
+    pub name: String,
+    pub pending: i64,
+    pub last_delivered_id: String,
+    /// Redis 7.0+
+    pub entries_read: Option<i64>,
+    /// Redis 7.0+, and unknown for a while after entries were deleted
+    pub lag: Option<i64>,
+    pub consumers: Vec<Consumer>,
+    /// The lowest and highest pending IDs
+    pub pending_range: Option<(String, String)>,
+    /// The pending entries with the lowest IDs
+    pub oldest_pending: Vec<Pending>,
+}
+
+/// XINFO CONSUMERS
+#[derive(Debug, Default, Serialize)]
+pub struct Consumer {
+    pub name: String,
+    pub pending: i64,
+    /// Since the consumer's last attempted interaction, e.g. a read that returned nothing
+    pub idle_ms: i64,
+    /// Since its last successful interaction (Redis 7.2+)
+    pub inactive_ms: Option<i64>,
+}
+
+/// An entry of XPENDING
+#[derive(Debug, Default, Serialize)]
+pub struct Pending {
+    pub id: String,
+    pub consumer: String,
+    pub idle_ms: usize,
+    pub deliveries: usize,
+}
+
+/// Inspect `stream` once, or every `watch` until Ctrl-C, showing the `oldest` pending entries of each group
+pub fn run(config: &Config, stream: &str, output: Output, watch: Option<Duration>, oldest: usize) -> rs_util::Result<()> {
+    let mut con = rs_util::connect(config)?;
+    let capabilities = rs_util::capabilities(&mut con)?;
+    capabilities.require(Feature::Streams)?;
+    // Older servers leave the entries read and the lag of the groups out
+    let has_lag = capabilities.supports(Feature::XInfoLag);
+    let shutdown = rs_util::shutdown_token();
+
+    loop {
+        let inspection = inspect(&mut con, stream, oldest)?;
+        match (output, watch) {
+            (Output::Json, None) => println!("{}", serde_json::to_string_pretty(&inspection).map_err(io::Error::from)?),
+            (Output::Json, Some(_)) => println!("{}", serde_json::to_string(&inspection).map_err(io::Error::from)?),
+            (Output::Tables, None) => print(&inspection, has_lag),
+            (Output::Tables, Some(every)) => {
+                // Clear the terminal and start at the top
+                print!("\x1b[2J\x1b[H");
+                println!("Every {:?}, Ctrl-C to stop\n", every);
+                print(&inspection, has_lag);
+            }
+        }
+        match watch {
+            Some(every) if !shutdown.sleep(every) => continue,
+            _ => return Ok(()),
+        }
+    }
+}
+
+/// Gather everything about `stream`
+pub fn inspect<C: ConnectionLike>(con: &mut C, stream: &str, oldest: usize) -> rs_util::Result<Inspection> {
+    let info: HashMap<String, Value> = redis::cmd("XINFO")
+        .arg("STREAM")
+        .arg(stream)
+        .query(con)
+        .with_context(|| format!("XINFO STREAM {}", stream))?;
+    let groups: Vec<HashMap<String, Value>> = redis::cmd("XINFO")
+        .arg("GROUPS")
+        .arg(stream)
+        .query(con)
+        .with_context(|| format!("XINFO GROUPS {}", stream))?;
+
+    let mut inspection = Inspection {
+        stream: stream.to_string(),
+        length: field(&info, "length").unwrap_or_default(),
+        radix_tree_keys: field(&info, "radix-tree-keys").unwrap_or_default(),
+        radix_tree_nodes: field(&info, "radix-tree-nodes").unwrap_or_default(),
+        last_generated_id: field(&info, "last-generated-id").unwrap_or_default(),
+        first_entry: entry_id(&info, "first-entry"),
+        last_entry: entry_id(&info, "last-entry"),
+        entries_added: field(&info, "entries-added"),
+        groups: vec![],
+    };
+    for group in &groups {
+        inspection.groups.push(self::group(con, stream, group, oldest)?);
+    }
+    Ok(inspection)
+}
+
+fn group<C: ConnectionLike>(con: &mut C, stream: &str, info: &HashMap<String, Value>, oldest: usize) -> rs_util::Result<Group> {
+    let name: String = field(info, "name").unwrap_or_default();
+    let consumers: Vec<HashMap<String, Value>> = redis::cmd("XINFO")
+        .arg("CONSUMERS")
+        .arg(stream)
+        .arg(&name)
+        .query(con)
+        .with_context(|| format!("XINFO CONSUMERS {} {}", stream, name))?;
+    let summary: StreamPendingReply = con
+        .xpending(stream, &name)
+        .with_context(|| format!("XPENDING {} {}", stream, name))?;
+    let pending: StreamPendingCountReply = con
+        .xpending_count(stream, &name, "-", "+", oldest)
+        .with_context(|| format!("XPENDING {} {} - + {}", stream, name, oldest))?;
+
+    Ok(Group {
+        pending: field(info, "pending").unwrap_or_default(),
+        last_delivered_id: field(info, "last-delivered-id").unwrap_or_default(),
+        entries_read: field(info, "entries-read"),
+        lag: field(info, "lag"),
+        consumers: consumers
+            .iter()
+            .map(|consumer| Consumer {
+                name: field(consumer, "name").unwrap_or_default(),
+                pending: field(consumer, "pending").unwrap_or_default(),
+                idle_ms: field(consumer, "idle").unwrap_or_default(),
+                inactive_ms: field(consumer, "inactive"),
+            })
+            .collect(),
+        pending_range: match summary {
+            StreamPendingReply::Data(data) => Some((data.start_id, data.end_id)),
+            StreamPendingReply::Empty => None,
+        },
+        oldest_pending: pending
+            .ids
+            .into_iter()
+            .map(|pending| Pending {
+                id: pending.id,
+                consumer: pending.consumer,
+                idle_ms: pending.last_delivered_ms,
+                deliveries: pending.times_delivered,
+            })
+            .collect(),
+        name,
+    })
+}
+
+/// A field of an XINFO reply, if the server has it and it isn't nil
+fn field<T: FromRedisValue>(info: &HashMap<String, Value>, name: &str) -> Option<T> {
+    info.get(name).and_then(|value| T::from_redis_value(value).ok())
+}
+
+/// The ID of the first or last entry of XINFO STREAM, which is nil for an empty stream
+fn entry_id(info: &HashMap<String, Value>, name: &str) -> Option<String> {
+    match info.get(name) {
+        Some(Value::Bulk(entry)) => entry.first().and_then(|id| String::from_redis_value(id).ok()),
+        _ => None,
+    }
+}
+
+fn print(inspection: &Inspection, has_lag: bool) {
+    println!("{} {}", "Stream".bold(), inspection.stream);
+    table(
+        &["LENGTH", "FIRST ENTRY", "LAST ENTRY", "LAST GENERATED", "ADDED", "RADIX KEYS", "RADIX NODES"],
+        vec![vec![
+            inspection.length.to_string(),
+            or_dash(&inspection.first_entry),
+            or_dash(&inspection.last_entry),
+            inspection.last_generated_id.clone(),
+            or_dash(&inspection.entries_added),
+            inspection.radix_tree_keys.to_string(),
+            inspection.radix_tree_nodes.to_string(),
+        ]],
+    );
+
+    println!("\n{} ({})", "Groups".bold(), inspection.groups.len());
+    table(
+        &["GROUP", "CONSUMERS", "PENDING", "LAST DELIVERED", "ENTRIES READ", "LAG"],
+        inspection
+            .groups
+            .iter()
+            .map(|group| {
+                vec![
+                    group.name.clone(),
+                    group.consumers.len().to_string(),
+                    group.pending.to_string(),
+                    group.last_delivered_id.clone(),
+                    or_dash(&group.entries_read),
+                    or_dash(&group.lag),
+                ]
+            })
+            .collect(),
+    );
+    if !has_lag {
+        println!("  The {} needs Redis {} or later", Feature::XInfoLag, Feature::XInfoLag.since());
+    }
+
+    for group in &inspection.groups {
+        println!("\n{} {}", "Consumers of".bold(), group.name);
+        table(
+            &["CONSUMER", "PENDING", "IDLE", "INACTIVE"],
+            group
+                .consumers
+                .iter()
+                .map(|consumer| {
+                    vec![
+                        consumer.name.clone(),
+                        consumer.pending.to_string(),
+                        elapsed(consumer.idle_ms as u64),
+                        consumer.inactive_ms.map_or("-".to_string(), |ms| elapsed(ms as u64)),
+                    ]
+                })
+                .collect(),
+        );
+
+        match &group.pending_range {
+            Some((first, last)) => println!(
+                "\n{} {}: {} in total, from {} to {}",
+                "Oldest pending entries of".bold(),
+                group.name,
+                group.pending,
+                first,
+                last
+            ),
+            None => println!("\n{} {}: none", "Pending entries of".bold(), group.name),
+        }
+        if !group.oldest_pending.is_empty() {
+            table(
+                &["ID", "CONSUMER", "IDLE", "DELIVERIES"],
+                group
+                    .oldest_pending
+                    .iter()
+                    .map(|pending| {
+                        vec![
+                            pending.id.clone(),
+                            pending.consumer.clone(),
+                            elapsed(pending.idle_ms as u64),
+                            pending.deliveries.to_string(),
+                        ]
+                    })
+                    .collect(),
+            );
+        }
+    }
+}
+
+/// Print the rows in columns as wide as their widest cell, under a bold header
+fn table(headers: &[&str], rows: Vec<Vec<String>>) {
+    let widths: Vec<usize> = headers
+        .iter()
+        .enumerate()
+        .map(|(i, header)| rows.iter().map(|row| row[i].len()).chain([header.len()]).max().unwrap_or(0))
+        .collect();
+    let line = |cells: Vec<String>| {
+        let cells: Vec<String> = cells.iter().zip(&widths).map(|(cell, width)| format!("{:<width$}", cell, width = width)).collect();
+        format!("  {}", cells.join("  ").trim_end())
+    };
+    println!("{}", line(headers.iter().map(|header| header.to_string()).collect()).bold());
+    for row in rows {
+        println!("{}", line(row));
+    }
+}
+
+fn or_dash<T: ToString>(value: &Option<T>) -> String {
+    value.as_ref().map_or("-".to_string(), ToString::to_string)
+}
+
+/// A time in milliseconds, e.g. `850ms`, `12.3s` or `5m 03s`
+fn elapsed(ms: u64) -> String {
+    match ms {
+        0..=999 => format!("{}ms", ms),
+        1000..=59_999 => format!("{:.1}s", ms as f64 / 1000.0),
+        _ => format!("{}m {:02}s", ms / 60_000, ms / 1000 % 60),
+    }
+}
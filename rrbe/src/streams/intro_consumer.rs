@@ -0,0 +1,76 @@
+use std::time::Duration;
+
+use redis::{Commands, streams};
+use hostname;
+use rs_util::streams::{Ack, AckMode, GroupWorker};
+use rs_util::{Config, Error, ResilientConnection, Retry};
+use tracing::{info, warn};
+
+pub const ABOUT: &str = "
+    Redis University 202 - Streams: Intro Lab
+        Consumer
+        Simulate consuming the stream as a single member of a consumer group
+        and writing the data to a data warehouse.";
+
+/// Entries are not acknowledged in this lab, so they stay pending in the group
+fn write_to_data_warehouse(entry: &streams::StreamId) -> rs_util::Result<Ack> {
+    info!(
+        postal_code = rs_util::entry_field::<i32>(entry, "postal_code")?,
+        current_temp = rs_util::entry_field::<i32>(entry, "current_temp")?,
+        "Written to data warehouse"
+    );
+    Ok(Ack::Keep)
+}
+
+pub fn run(config: &Config) -> rs_util::Result<()> {
+    // A read of new entries is not safe to repeat: if the connection breaks before the reply
+    // arrives, the server has delivered the entries anyway, so they are pending for this consumer
+    // and a second read returns the ones after them.  Commands are not retried, see below.
+    // Ctrl-C or SIGTERM stops the consumer once the entry at hand is written, or while it
+    // waits for the server.  A blocking read is not interrupted, so that takes up to block_ms.
+    let shutdown = rs_util::shutdown_token();
+    let mut con = ResilientConnection::new(config.clone()).retry(Retry::Never).shutdown(shutdown.clone());
+
+    // Set up information for the consumer group
+    let stream_key = super::WEATHER;  // name of the stream to read from
+    let group_name = "data_warehouse_writer";   // name of the consumer group
+    let consumer_name = format!("consumer-{:?}-a", hostname::get()?);   // name of this consumer
+    let block_ms = 5000;    // the amount of time this consumer will block while waiting for data from the stream
+                            // before releasing the connection
+    // The consumer reads only entries in the stream that were never delivered to any other consumer
+    // in its group: the entries it has read before are never acknowledged, so it doesn't recover them.
+    // Only a reading that can't be decoded is acknowledged, once it was moved to {stream:weather}:dlq.
+    let worker = GroupWorker::new(stream_key, group_name, &consumer_name)
+        .block(Duration::from_millis(block_ms))
+        .recover_pending(false)
+        .ack_mode(AckMode::Never)
+        .dead_letter(3)
+        .shutdown(shutdown);
+
+    // Make sure that the stream exists, if not exit with an error code, instead of 0.
+    if !con.exists(stream_key)? {
+        return Err(Error::Other(format!("Stream {} does not exist.  Try running the producer first.", stream_key)));
+    }
+
+    // With --metrics-addr, report the stream's length and the group's pending entries and lag
+    rs_util::metrics().watch(stream_key);
+
+    // Attempt to create the group.  If the group already exists, tell the user.
+    if !worker.create_group(&mut con)? {
+        info!("Group {} already exists.", group_name);
+    }
+
+    loop {
+        match worker.run(&mut con, &mut write_to_data_warehouse) {
+            Ok(_) => break,
+            // The entries of a lost reply stay pending, and this consumer never reads those back,
+            // so they are skipped.  The next read re-establishes the connection.
+            Err(e) if matches!(e.root(), Error::Connection(_)) => {
+                warn!("{}; the entries of a lost read stay pending in group {}", e, group_name)
+            }
+            Err(e) => return Err(e),
+        }
+    }
+    info!("Shutdown signal received.  Good-bye!");
+    Ok(())
+}
@@ -0,0 +1,98 @@
+use std::time::Instant;
+
+use redis::{AsyncCommands, RedisResult, streams};
+use hostname;
+use rs_util::{Config, EntryId, Error, Feature};
+use tracing::{info, info_span, warn, Instrument};
+
+pub const ABOUT: &str = "
+    Redis University 202 - Streams: Intro Lab
+        Consumer (async)
+        Simulate consuming the stream as a single member of a consumer group
+        and writing the data to a data warehouse.
+        Press Ctrl-C to stop.";
+
+/// Entries are not acknowledged in this lab, so they stay pending in the group
+fn write_to_data_warehouse(data: &streams::StreamReadReply, group_name: &str) -> rs_util::Result<()> {
+    for stream in &data.keys {
+        rs_util::metrics().consumed(&stream.key, group_name, stream.ids.len());
+        for id in &stream.ids {
+            let _entry = info_span!("entry", stream = %stream.key, id = %id.id).entered();
+            let start = Instant::now();
+            info!(
+                postal_code = rs_util::entry_field::<i32>(id, "postal_code")?,
+                current_temp = rs_util::entry_field::<i32>(id, "current_temp")?,
+                "Written to data warehouse"
+            );
+            rs_util::metrics().observe_processing(&stream.key, group_name, start.elapsed());
+        }
+    }
+    Ok(())
+}
+
+pub async fn run(config: &Config) -> rs_util::Result<()> {
+    let group_name = "data_warehouse_writer";   // name of the consumer group
+    let consumer_name = format!("consumer-{:?}-a", hostname::get()?);   // name of this consumer
+    // Every event of the consumer carries its group and name, across the awaits
+    let span = info_span!("consumer", group = group_name, name = %consumer_name);
+    consume(config, group_name, consumer_name).instrument(span).await
+}
+
+async fn consume(config: &Config, group_name: &str, consumer_name: String) -> rs_util::Result<()> {
+    // The blocking XREADGROUP below holds up every other command on a multiplexed connection,
+    // so the consumer gets one to itself.
+    let mut con = rs_util::get_async_connection(config).await?;
+
+    // Set up information for the consumer group
+    let stream_key = super::WEATHER;  // name of the stream to read from
+    let block_ms = 5000;    // the amount of time this consumer will block while waiting for data from the stream
+                            // before releasing the connection
+    let stream_offsets = EntryId::Undelivered;  // the consumer will read only entries in the stream that were never delivered to
+                                                // any other consumer in its group
+    let stream_read_options = streams::StreamReadOptions::default()
+                                .block(block_ms)
+                                .group(group_name, consumer_name);
+
+    // Make sure that the stream exists, if not exit with an error code, instead of 0.
+    if !con.exists(stream_key).await? {
+        return Err(Error::Other(format!("Stream {} does not exist.  Try running the producer first.", stream_key)));
+    }
+
+    // Consumer groups need Redis 5.0.  Say so, instead of mistaking "unknown command" for an existing group.
+    rs_util::aio::capabilities(&mut con).await?.require(Feature::Streams)?;
+
+    // With --metrics-addr, report the stream's length and the group's pending entries and lag
+    rs_util::metrics().watch(stream_key);
+
+    // Attempt to create the group.  If the group already exists, tell the user.
+    let result: RedisResult<String> = con.xgroup_create(stream_key, group_name, 0).await;
+    match result {
+        Ok(_) => (),
+        Err(e) if e.code() == Some("BUSYGROUP") => info!("Group {} already exists.", group_name),
+        Err(e) => return Err(e.into()),
+    }
+
+    let keys = [stream_key];
+    let offsets = [stream_offsets];
+    let shutdown = rs_util::aio::shutdown_signal();
+    tokio::pin!(shutdown);
+
+    loop {
+        // Wait for new entries and the shutdown signal at the same time.
+        // There is no need to sleep between reads, because XREADGROUP blocks for up to block_ms.
+        tokio::select! {
+            results = con.xread_options::<_, _, streams::StreamReadReply>(&keys, &offsets, &stream_read_options) => {
+                match results {
+                    Ok(data) => write_to_data_warehouse(&data, group_name)?,
+                    Err(e) => warn!(error = %e, "Reading from the stream failed")
+                }
+            }
+            _ = &mut shutdown => {
+                info!("Shutdown signal received.  Good-bye!");
+                break;
+            }
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,71 @@
+use std::time::Duration;
+
+use redis::Commands;
+use redis::streams::StreamId;
+use hostname;
+use rs_util::streams::{Ack, AckMode, GroupWorker};
+use rs_util::window::{KeyedWindows, Span, Window};
+use rs_util::{Config, Error};
+use tracing::info;
+
+pub const ABOUT: &str = "
+    Redis University 202 - Streams: Intro Lab
+        Consumer Average
+        Simulate consuming the stream as a single member of a consumer group
+        and calculating the rolling window average of the temperature
+        of each postal code.";
+
+pub fn run(config: &Config) -> rs_util::Result<()> {
+    let mut con = rs_util::connect(config)?;
+
+    // Set up information for the consumer group
+    let stream_key = super::WEATHER;  // name of the stream to read from
+    let group_name = "rolling_average_printer";   // name of the consumer group
+    // name of this consumer
+    // Note: If we are running the consumer app and this app from the same host, the consumer names will be
+    //       identical.  This is okay because only the consumer name is unique within the consumer group.
+    let consumer_name = format!("consumer-{:?}-a", hostname::get()?);
+    let block_ms = 5000;    // the amount of time this consumer will block while waiting for data from the stream
+                            // before releasing the connection
+    // Ctrl-C or SIGTERM stops the consumer once the entry at hand is processed.
+    // A blocking read is not interrupted, so that takes up to block_ms.
+    // The consumer reads only entries in the stream that were never delivered to any other consumer
+    // in its group, and, like the consumer lab, never acknowledges them.
+    // Only a reading that can't be decoded is acknowledged, once it was moved to {stream:weather}:dlq.
+    let worker = GroupWorker::new(stream_key, group_name, &consumer_name)
+        .block(Duration::from_millis(block_ms))
+        .recover_pending(false)
+        .ack_mode(AckMode::Never)
+        .dead_letter(3)
+        .shutdown(rs_util::shutdown_token());
+
+    // Make sure that the stream exists, if not exit with an error code, instead of 0.
+    if !con.exists(stream_key)? {
+        return Err(Error::Other(format!("Stream {} does not exist.  Try running the producer first.", stream_key)));
+    }
+
+    // With --metrics-addr, report the stream's length and the group's pending entries and lag
+    rs_util::metrics().watch(stream_key);
+
+    // Attempt to create the group.  If the group already exists, tell the user.
+    if !worker.create_group(&mut con)? {
+        info!("Group {} already exists.", group_name);
+    }
+
+    // Calculate and display the rolling window average of the postal code as each message is read from the stream
+    let window_size = 10;
+    let mut windows = KeyedWindows::new(Window::sliding(Span::Count(window_size)));
+
+    worker.run(&mut con, &mut |entry: &StreamId| {
+        let postal_code: i32 = rs_util::entry_field(entry, "postal_code")?;
+        let current_temp: i32 = rs_util::entry_field(entry, "current_temp")?;
+        // Show the data that was processed and the rolling window average
+        windows.push(&postal_code, entry.id.parse()?, current_temp);
+        let rolling_average = windows.get(&postal_code).and_then(Window::mean).unwrap_or_default();
+        info!(postal_code, current_temp, rolling_average, "Processed");
+        Ok(Ack::Keep)
+    })?;
+
+    info!("Shutdown signal received.  Good-bye!");
+    Ok(())
+}
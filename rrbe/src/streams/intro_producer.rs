@@ -0,0 +1,90 @@
+use std::time::Duration;
+
+use redis::RedisResult;
+use rand::prelude::*;
+
+use rs_util::{Config, Context, ResilientConnection, Retention, Retry};
+use tracing::{info, warn};
+
+pub const ABOUT: &str = "
+    Redis University 202 - Streams: Intro Lab
+            Producer
+            Simulate distributed temperature sensors streaming data";
+
+const POSTAL_CODES: [i32; 4] = [94016, 80014, 60659, 10011];
+const MAX_TEMP: i32 = 100;
+const MIN_TEMP: i32 = 0;
+
+#[derive(Debug)]
+struct Measurement {
+    postal_code: i32,
+    current_temp: i32,
+}
+
+impl Measurement {
+    pub fn new() -> Measurement {
+        Measurement { postal_code: POSTAL_CODES[0],
+                      current_temp: 50}
+    }
+
+    pub fn get_next(&mut self) -> &Self {
+        let mut rng = thread_rng();
+        let rnd: f64 = rng.gen();
+        if rnd >= 0.5 {
+            if self.current_temp < MAX_TEMP {
+                self.current_temp += 1;
+            } 
+        } else {
+            if self.current_temp > MIN_TEMP {
+                self.current_temp -= 1;
+            }
+        }
+        
+        self
+    }
+
+    pub fn to_stream_data(&self) -> Vec<(String, String)> {
+        vec![(String::from("postal_code"), self.postal_code.to_string()),
+        (String::from("current_temp"), self.current_temp.to_string())]
+    }
+}
+
+pub fn run(config: &Config, mut retention: Retention) -> rs_util::Result<()> {
+    // Keep producing across server restarts: the connection re-establishes itself with backoff
+    // Ctrl-C or SIGTERM stops the producer between two measurements, or while it waits for the server
+    let shutdown = rs_util::shutdown_token();
+    let mut con = ResilientConnection::new(config.clone()).shutdown(shutdown.clone());
+
+    // Set key's value
+    let stream_key = super::WEATHER;
+    let mut measurement = Measurement::new();
+    // With --metrics-addr, report the stream's length and its groups' pending entries and lag
+    rs_util::metrics().watch(stream_key);
+    // MINID, for --min-id and --max-age, needs Redis 6.2
+    retention.check(&mut con)?;
+    while !shutdown.is_triggered() {
+        let entry = measurement.get_next();
+        // XADD is not retried: if the connection broke after the server added the entry,
+        // sending it again would write the measurement twice.
+        let result: RedisResult<String> = retention
+            .xadd(stream_key, &entry.to_stream_data()[..])
+            .query(&mut con.with_retry(Retry::Never));
+        match result {
+            Ok(id) => {
+                info!(%id, postal_code = entry.postal_code, current_temp = entry.current_temp, "Wrote the measurement");
+                rs_util::metrics().produced(stream_key);
+            }
+            Err(e) => warn!(postal_code = entry.postal_code, current_temp = entry.current_temp, error = %e,
+                "The measurement was not written"),
+        }
+        // With --trim-every, the stream is trimmed now and then rather than on every XADD
+        if let Some(trim) = retention.trim_due(stream_key) {
+            let trimmed: usize = trim.query(&mut con).context("trimming the stream")?;
+            info!(trimmed, "Trimmed the stream");
+        }
+        shutdown.sleep(Duration::from_secs(1));
+    }
+
+    info!("Shutdown signal received.  Good-bye!");
+    Ok(())
+}
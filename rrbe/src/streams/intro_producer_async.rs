@@ -0,0 +1,91 @@
+use std::time::Duration;
+
+use rand::prelude::*;
+use tokio::time;
+use rs_util::{Config, Context, Retention};
+use tracing::info;
+
+pub const ABOUT: &str = "
+    Redis University 202 - Streams: Intro Lab
+            Producer (async)
+            Simulate distributed temperature sensors streaming data.
+            Press Ctrl-C to stop.";
+
+const POSTAL_CODES: [i32; 4] = [94016, 80014, 60659, 10011];
+const MAX_TEMP: i32 = 100;
+const MIN_TEMP: i32 = 0;
+
+#[derive(Debug)]
+struct Measurement {
+    postal_code: i32,
+    current_temp: i32,
+}
+
+impl Measurement {
+    pub fn new() -> Measurement {
+        Measurement { postal_code: POSTAL_CODES[0],
+                      current_temp: 50}
+    }
+
+    pub fn get_next(&mut self) -> &Self {
+        let mut rng = thread_rng();
+        let rnd: f64 = rng.gen();
+        if rnd >= 0.5 {
+            if self.current_temp < MAX_TEMP {
+                self.current_temp += 1;
+            } 
+        } else {
+            if self.current_temp > MIN_TEMP {
+                self.current_temp -= 1;
+            }
+        }
+        
+        self
+    }
+
+    pub fn to_stream_data(&self) -> Vec<(String, String)> {
+        vec![(String::from("postal_code"), self.postal_code.to_string()),
+        (String::from("current_temp"), self.current_temp.to_string())]
+    }
+}
+
+pub async fn run(config: &Config, mut retention: Retention) -> rs_util::Result<()> {
+    let mut con = rs_util::get_async_connection(config).await?;
+    // MINID, for --min-id and --max-age, needs Redis 6.2
+    if let Some(feature) = retention.feature() {
+        rs_util::aio::capabilities(&mut con).await?.require(feature)?;
+    }
+
+    let stream_key = super::WEATHER;
+    let mut measurement = Measurement::new();
+    // With --metrics-addr, report the stream's length and its groups' pending entries and lag
+    rs_util::metrics().watch(stream_key);
+
+    // Instead of sleeping between entries, wait for the next tick of an interval,
+    // so that the producer can react to a shutdown signal at any time.
+    let mut ticks = time::interval(Duration::from_secs(1));
+    let shutdown = rs_util::aio::shutdown_signal();
+    tokio::pin!(shutdown);
+
+    loop {
+        tokio::select! {
+            _ = ticks.tick() => {
+                let entry = measurement.get_next();
+                let id: String = retention.xadd(stream_key, &entry.to_stream_data()[..]).query_async(&mut con).await?;
+                info!(%id, postal_code = entry.postal_code, current_temp = entry.current_temp, "Wrote the measurement");
+                rs_util::metrics().produced(stream_key);
+                // With --trim-every, the stream is trimmed now and then rather than on every XADD
+                if let Some(trim) = retention.trim_due(stream_key) {
+                    let trimmed: usize = trim.query_async(&mut con).await.context("trimming the stream")?;
+                    info!(trimmed, "Trimmed the stream");
+                }
+            }
+            _ = &mut shutdown => {
+                info!("Shutdown signal received.  Good-bye!");
+                break;
+            }
+        }
+    }
+
+    Ok(())
+}
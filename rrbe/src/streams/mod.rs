@@ -0,0 +1,25 @@
+//! Redis University 202 - Streams
+
+/// The stream of natural numbers, written by producer_1, producer_2 and the consumer group lab
+pub const NUMBERS: &str = "numbers";
+/// The stream of temperature readings of the intro lab
+pub const WEATHER: &str = "stream:weather";
+
+pub mod consumer_group;
+pub mod consumer_group_consumer;
+pub mod consumer_group_consumer_async;
+pub mod dlq;
+pub mod export;
+pub mod helloworld;
+pub mod import;
+pub mod inspect;
+pub mod intro_consumer;
+pub mod intro_consumer_async;
+pub mod intro_consumer_average;
+pub mod intro_producer;
+pub mod intro_producer_async;
+pub mod producer_1;
+pub mod producer_2;
+pub mod range_1;
+pub mod replay;
+pub mod test_connection;
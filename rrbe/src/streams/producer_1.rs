@@ -0,0 +1,70 @@
+//! The simplest Natural Numbers Stream producer
+//! Every run starts the count of numbers from zero
+
+use std::time::Duration;
+
+use redis::{Commands, RedisResult};
+use rs_util::{Config, Context, ResilientConnection, Retention, Retry};
+use tracing::{info, warn};
+
+pub const ABOUT: &str = "
+    Redis University 202 - Streams: producer_1
+            A very simple app to write the list of natural numbers to a 
+            stream named numbers.  Without --maxlen, --min-id or --max-age, watch the
+            stream grow without bound.";
+
+pub fn run(config: &Config, mut retention: Retention) -> rs_util::Result<()> {
+    // Ctrl-C or SIGTERM stops the producer between two numbers, or while it waits for the server
+    let shutdown = rs_util::shutdown_token();
+    let mut con = ResilientConnection::new(config.clone()).shutdown(shutdown.clone());
+    let stream_name = super::NUMBERS;
+    let mut n = 1;
+
+    // Make sure the stream does not exist before writing data to it
+    let _: u8 = con.del(stream_name)
+        .with_context(|| format!("deleting stream {}", stream_name))?;
+    // MINID, for --min-id and --max-age, needs Redis 6.2
+    retention.check(&mut con)?;
+    
+    while !shutdown.is_triggered() {
+        // Write data to stream.  XADD is never retried, so that no number is written twice;
+        // after a failure the same number is tried again once the connection is back.
+        let result: RedisResult<String> = retention
+            .xadd(stream_name, &[("n".to_string(), n.to_string())])
+            .query(&mut con.with_retry(Retry::Never));
+        match result {
+            Ok(id) => {
+                info!(stream = %stream_name, n, %id, "Produced");
+                n += 1;
+            }
+            Err(e) => {
+                warn!(stream = %stream_name, n, error = %e, "Failed to write the number");
+                shutdown.sleep(Duration::from_secs(1));
+                continue;
+            }
+        }
+
+        // With --trim-every, the stream is trimmed now and then rather than on every XADD
+        if let Some(trim) = retention.trim_due(stream_name) {
+            let trimmed: usize = trim.query(&mut con).context("trimming the stream")?;
+            info!(stream = %stream_name, trimmed, "Trimmed the stream");
+        }
+
+        // Obtain educational Stream growth statistics
+        let length: i64 = con
+            .xlen(stream_name)
+            .context("reading the stream length")?;
+        let usage: i64 = redis::cmd("MEMORY")
+            .arg("USAGE")
+            .arg(stream_name)
+            .query(&mut con)
+            .context("reading the stream's memory usage")?;
+        info!(stream = %stream_name, length, bytes = usage, "Stream size");
+
+        // Pause the processing for 1 second.  This could be randomized or removed, depending on need.
+        shutdown.sleep(Duration::from_secs(1));
+    }
+
+    info!("Shutdown signal received.  Good-bye!");
+    Ok(())
+}
@@ -0,0 +1,38 @@
+//! Natural Numbers Stream Producer: 0 to 100
+//! The stream is removed before each run.
+//! Each run begins at 0 and ends at 100.
+
+use redis::Commands;
+use rs_util::{Config, Context, Retention};
+use tracing::info;
+
+pub const ABOUT: &str = "
+    Redis University 202 - Streams: producer_2
+            A very simple app to write the natural numbers from 0 to 100
+            to a stream named numbers.";
+
+pub fn run(config: &Config, mut retention: Retention) -> rs_util::Result<()> {
+    let mut con = rs_util::connect(config)?;
+    let stream_name = super::NUMBERS;
+
+    // Make sure the stream does not exist before writing data to it
+    let _: u8 = con.del(stream_name)
+        .with_context(|| format!("deleting stream {}", stream_name))?;
+    // MINID, for --min-id and --max-age, needs Redis 6.2
+    retention.check(&mut con)?;
+    
+    for n in 0..=100 {
+        // Write data to stream
+        let id: String = retention
+            .xadd(stream_name, &[("n".to_string(), n.to_string())])
+            .query(&mut con)
+            .with_context(|| format!("writing number {} to stream {}", n, stream_name))?;
+        info!(stream = %stream_name, n, %id, "Produced");
+        if let Some(trim) = retention.trim_due(stream_name) {
+            let trimmed: usize = trim.query(&mut con).context("trimming the stream")?;
+            info!(stream = %stream_name, trimmed, "Trimmed the stream");
+        }
+    }
+
+    Ok(())
+}
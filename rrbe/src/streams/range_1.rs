@@ -0,0 +1,40 @@
+//! Sum the numbers in the Stream of natural numbers
+//! using range queries
+
+use rs_util::{Config, RangePages, TimeRange};
+use tracing::{debug, info};
+
+pub const ABOUT: &str = "
+    Redis University 202 - Streams: range_1
+            Produce the sum of the natural numbers from the stream of
+            natural numbers created by either producer_1 or producer_2
+            using xrange queries.  --since and --until sum the numbers added
+            during a while only, e.g. --since 5m for the last five minutes.";
+
+pub fn run(config: &Config, range: TimeRange) -> rs_util::Result<()> {
+    // Connect to the configured server
+    let mut con = rs_util::connect(config)?;
+
+    let stream_name = super::NUMBERS;
+    let start = range.start();  // By default the lowest valid full message ID in a Stream
+    let end = range.end().into();
+    let count = 5;
+    let mut n_sum = 0;
+
+    // Read messages from the stream and produce the running sum until there are no more
+    // entries in the stream.  Each page starts right after the last entry of the previous one.
+    for entries in RangePages::new(&mut con, stream_name, start, end, count) {
+        // Process each entry read from the stream, adding its value to the running sum
+        for entry in entries? {
+            debug!(id = %entry.id, "Read");
+            n_sum += rs_util::entry_field::<i64>(&entry, "n")?;
+        }
+
+        info!(sum = n_sum, "The sum of the Natural Numbers Stream");
+    }
+
+    // An empty page means we have exhausted the Stream
+    info!(stream = %stream_name, "We have exhausted the stream. Good-bye!");
+
+    Ok(())
+}
@@ -0,0 +1,99 @@
+//! Replay a recorded stream into another one, at the pace its entries were added
+
+use std::time::{Duration, Instant};
+
+use redis::Value;
+use rs_util::{Config, ConfigError, Context, EntryId, Pacing, RangePages, Retention, StreamId};
+use tracing::{debug, info};
+
+pub const ABOUT: &str = "
+    Redis University 202 - Streams: Replay
+            Add the entries of SOURCE, e.g. stream:weather, to DESTINATION as new entries,
+            as far apart as the milliseconds of their IDs tell, for load tests.  --speed 10x
+            replays ten times faster, --loop starts over at the end of SOURCE until Ctrl-C,
+            and --rewrite-time FIELD moves the timestamps in FIELD to the time of the replay.";
+
+/// How to replay a stream
+#[derive(Clone, Debug)]
+pub struct Options {
+    /// 1.0 for the original pace, see `Pacing`
+    pub speed: f64,
+    /// Start over at the end of the source
+    pub repeat: bool,
+    /// Fields with timestamps to rewrite, in seconds or milliseconds since the epoch
+    pub rewrite_time: Vec<String>,
+}
+
+/// Entries read per XRANGE
+const PAGE: usize = 100;
+
+/// Replay `source` into `destination`, which is trimmed as `retention` says
+pub fn run(config: &Config, source: &str, destination: &str, options: &Options, mut retention: Retention) -> rs_util::Result<()> {
+    if source == destination {
+        return Err(ConfigError::from("The source and the destination must be different streams".to_string()).into());
+    }
+    // One connection pages through the source while the other writes
+    let mut reader = rs_util::connect(config)?;
+    let mut writer = rs_util::connect(config)?;
+    retention.check(&mut writer)?;
+    let shutdown = rs_util::shutdown_token();
+
+    let mut pacing = Pacing::new(options.speed);
+    let mut total = 0;
+    let mut run = 0;
+    loop {
+        run += 1;
+        let mut replayed = 0;
+        for page in RangePages::new(&mut reader, source, StreamId::MIN, EntryId::Max, PAGE) {
+            for entry in page? {
+                let id: StreamId = entry.id.parse()?;
+                // An entry too far ahead to wait for is only replayed after Ctrl-C, that is never
+                let wait = pacing.due(id).map_or(Duration::MAX, |due| due.saturating_duration_since(Instant::now()));
+                if shutdown.sleep(wait) {
+                    info!(replayed = total, "Shutdown signal received.  Good-bye!");
+                    return Ok(());
+                }
+
+                let fields: Vec<(&str, Vec<u8>)> = entry
+                    .map
+                    .iter()
+                    .filter_map(|(field, value)| match value {
+                        Value::Data(bytes) => Some((field.as_str(), rewrite(&pacing, options, field, bytes))),
+                        _ => None,
+                    })
+                    .collect();
+                let new_id: String = retention
+                    .xadd(destination, &fields)
+                    .query(&mut writer)
+                    .with_context(|| format!("replaying entry {} to {}", entry.id, destination))?;
+                debug!(id = %entry.id, %new_id, "Replayed");
+                replayed += 1;
+                total += 1;
+            }
+            if let Some(trim) = retention.trim_due(destination) {
+                let trimmed: usize = trim.query(&mut writer).context("trimming the destination")?;
+                info!(trimmed, "Trimmed the stream");
+            }
+        }
+
+        if replayed == 0 {
+            return Err(rs_util::Error::Other(format!("{} has no entries to replay", source)));
+        }
+        info!(run, replayed, source = %source, destination = %destination, "Replayed the stream");
+        if !options.repeat {
+            return Ok(());
+        }
+        pacing.restart();
+    }
+}
+
+/// The value of a field, with its timestamp rewritten if it is one of the fields to rewrite
+fn rewrite(pacing: &Pacing, options: &Options, field: &str, value: &[u8]) -> Vec<u8> {
+    let timestamp = std::str::from_utf8(value).ok().and_then(|text| text.parse().ok());
+    match timestamp {
+        Some(timestamp) if options.rewrite_time.iter().any(|name| name == field) => {
+            pacing.rewrite(timestamp).map_or_else(|| value.to_vec(), |rewritten| rewritten.to_string().into_bytes())
+        }
+        _ => value.to_vec(),
+    }
+}
@@ -0,0 +1,340 @@
+//! Check the connection to the server and report on everything the examples depend on:
+//! name resolution, authentication, server version and role, latency, persistence and
+//! which Stream commands the server knows.
+
+use std::io;
+use std::net::{SocketAddr, TcpStream, ToSocketAddrs};
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+use colored::*;
+use redis::{Connection, ConnectionAddr, InfoDict};
+use rs_util::{Config, Error, Feature, Version};
+use serde::Serialize;
+
+pub const ABOUT: &str = "
+    Redis University 202 - Streams: test_connection
+            Diagnose the connection to the server: DNS, TCP connect time, authentication,
+            server version and role, key count, latency, persistence and Stream features.
+            Every check prints a PASS, WARN or FAIL line; --json prints the report as JSON.
+            The exit code is 1 if any check failed.";
+
+/// How long to wait for the TCP handshake
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(3);
+/// The number of PINGs the latency percentiles are computed from
+const LATENCY_SAMPLES: usize = 100;
+/// A p99 round trip above this is reported as a warning
+const SLOW_P99: Duration = Duration::from_millis(10);
+
+/// How the report is printed
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Output {
+    /// One colored PASS/WARN/FAIL line per check
+    Text,
+    /// A single JSON object, for scripts
+    Json,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Status {
+    Pass,
+    Warn,
+    Fail,
+}
+
+/// The result of one check
+#[derive(Debug, Serialize)]
+pub struct Check {
+    pub name: &'static str,
+    pub status: Status,
+    pub detail: String,
+}
+
+/// All the checks that were run, in order
+#[derive(Debug, Default, Serialize)]
+pub struct Report {
+    pub ok: bool,
+    pub checks: Vec<Check>,
+}
+
+impl Report {
+    fn add(&mut self, name: &'static str, status: Status, detail: String) {
+        self.checks.push(Check { name, status, detail });
+    }
+
+    fn pass(&mut self, name: &'static str, detail: String) {
+        self.add(name, Status::Pass, detail)
+    }
+
+    fn warn(&mut self, name: &'static str, detail: String) {
+        self.add(name, Status::Warn, detail)
+    }
+
+    fn fail(&mut self, name: &'static str, detail: String) {
+        self.add(name, Status::Fail, detail)
+    }
+
+    fn failures(&self) -> usize {
+        self.checks.iter().filter(|check| check.status == Status::Fail).count()
+    }
+}
+
+pub fn run(config: &Config, output: Output) -> rs_util::Result<()> {
+    let mut report = diagnose(config);
+    let failures = report.failures();
+    report.ok = failures == 0;
+
+    match output {
+        Output::Text => {
+            for check in &report.checks {
+                let status = match check.status {
+                    Status::Pass => "PASS".green(),
+                    Status::Warn => "WARN".yellow(),
+                    Status::Fail => "FAIL".red(),
+                };
+                println!("[{}] {:<12} {}", status, check.name, check.detail);
+            }
+        }
+        Output::Json => println!("{}", serde_json::to_string_pretty(&report).map_err(io::Error::from)?),
+    }
+
+    if failures > 0 {
+        return Err(Error::Other(format!("{} check(s) failed", failures)));
+    }
+    Ok(())
+}
+
+/// Run the checks.  A failure that makes the following checks pointless ends the report.
+pub fn diagnose(config: &Config) -> Report {
+    let mut report = Report::default();
+
+    let node = match node(config, &mut report) {
+        Some(node) => node,
+        None => return report,
+    };
+    if !reachable(&node, &mut report) {
+        return report;
+    }
+
+    let mut con = match rs_util::get_connection(&node) {
+        Ok(con) => con,
+        Err(e) => {
+            report.fail("auth", format!("Could not open the connection: {}", e));
+            return report;
+        }
+    };
+    match redis::cmd("ACL").arg("WHOAMI").query::<String>(&mut con) {
+        Ok(user) => report.pass("auth", format!("Authenticated as '{}'", user)),
+        // Redis before 6.0 has no ACLs, and an ACL may deny the command
+        Err(e) => report.warn("auth", format!("Connected, but ACL WHOAMI failed: {}", e)),
+    }
+
+    let info: InfoDict = match redis::cmd("INFO").query(&mut con) {
+        Ok(info) => info,
+        Err(e) => {
+            report.fail("info", format!("INFO failed: {}", e));
+            return report;
+        }
+    };
+    server(&info, &mut report);
+    role(&info, &mut report);
+    keys(&mut con, node.db, &mut report);
+    latency(&mut con, &mut report);
+    persistence(&mut con, &info, &mut report);
+    streams(&mut con, &mut report);
+    report
+}
+
+/// The node to run the checks against: the master for Sentinel, the first seed node of a cluster
+fn node(config: &Config, report: &mut Report) -> Option<Config> {
+    if let Some(sentinel) = &config.sentinel {
+        return match rs_util::sentinel::master_addr(sentinel) {
+            Ok((host, port)) => {
+                report.pass("sentinel", format!("Master '{}' is at {}:{}", sentinel.master_name, host, port));
+                Some(Config { host, port, sentinel: None, ..config.clone() })
+            }
+            Err(e) => {
+                report.fail("sentinel", format!("No Sentinel knows master '{}': {}", sentinel.master_name, e));
+                None
+            }
+        };
+    }
+    if let Some(seeds) = &config.cluster {
+        let (host, port) = seeds[0].clone();
+        report.pass("cluster", format!("Checking seed node {}:{} of {}", host, port, seeds.len()));
+        return Some(Config { host, port, cluster: None, ..config.clone() });
+    }
+    Some(config.clone())
+}
+
+/// Resolve the host name and time the TCP handshake, or check that the Unix socket exists
+fn reachable(node: &Config, report: &mut Report) -> bool {
+    let (host, port) = match node.connection_info().map(|info| info.addr) {
+        Ok(ConnectionAddr::Tcp(host, port)) | Ok(ConnectionAddr::TcpTls { host, port, .. }) => (host, port),
+        Ok(ConnectionAddr::Unix(path)) => return socket(&path, report),
+        Err(e) => {
+            report.fail("address", e.to_string());
+            return false;
+        }
+    };
+
+    let start = Instant::now();
+    let addrs: Vec<SocketAddr> = match (host.as_str(), port).to_socket_addrs() {
+        Ok(addrs) => addrs.collect(),
+        Err(e) => {
+            report.fail("dns", format!("Could not resolve {}: {}", host, e));
+            return false;
+        }
+    };
+    if addrs.is_empty() {
+        report.fail("dns", format!("{} has no addresses", host));
+        return false;
+    }
+    let list: Vec<String> = addrs.iter().map(|addr| addr.ip().to_string()).collect();
+    report.pass("dns", format!("{} resolves to {} ({})", host, list.join(", "), millis(start.elapsed())));
+
+    let start = Instant::now();
+    match TcpStream::connect_timeout(&addrs[0], CONNECT_TIMEOUT) {
+        Ok(_) => {
+            report.pass("tcp", format!("Connected to {} in {}", addrs[0], millis(start.elapsed())));
+            true
+        }
+        Err(e) => {
+            report.fail("tcp", format!("Could not connect to {}: {}", addrs[0], e));
+            false
+        }
+    }
+}
+
+fn socket(path: &Path, report: &mut Report) -> bool {
+    if path.exists() {
+        report.pass("socket", format!("{} exists", path.display()));
+        true
+    } else {
+        report.fail("socket", format!("{} does not exist", path.display()));
+        false
+    }
+}
+
+fn server(info: &InfoDict, report: &mut Report) {
+    let version: String = info.get("redis_version").unwrap_or_default();
+    let mode: String = info.get("redis_mode").unwrap_or_else(|| "unknown".to_string());
+    let detail = format!("Redis {}, {} mode", version, mode);
+    if Version::parse(&version) < Feature::Streams.since() {
+        report.fail("server", format!("{}; Streams need Redis {} or later", detail, Feature::Streams.since()));
+    } else {
+        report.pass("server", detail);
+    }
+}
+
+fn role(info: &InfoDict, report: &mut Report) {
+    let role: String = info.get("role").unwrap_or_else(|| "unknown".to_string());
+    if role == "master" {
+        let replicas: u32 = info.get("connected_slaves").unwrap_or(0);
+        report.pass("role", format!("master with {} connected replica(s)", replicas));
+    } else {
+        let master_host: String = info.get("master_host").unwrap_or_default();
+        let master_port: String = info.get("master_port").unwrap_or_default();
+        let link: String = info.get("master_link_status").unwrap_or_else(|| "unknown".to_string());
+        report.warn(
+            "role",
+            format!("{} of {}:{} (link {}); the producers can't write here", role, master_host, master_port, link),
+        );
+    }
+}
+
+fn keys(con: &mut Connection, db: i64, report: &mut Report) {
+    match redis::cmd("DBSIZE").query::<u64>(con) {
+        Ok(count) => report.pass("keys", format!("DB {} holds {} key(s)", db, count)),
+        Err(e) => report.warn("keys", format!("DBSIZE failed: {}", e)),
+    }
+}
+
+/// Time a series of PINGs and report the percentiles of the round trip
+fn latency(con: &mut Connection, report: &mut Report) {
+    let mut samples = Vec::with_capacity(LATENCY_SAMPLES);
+    for _ in 0..LATENCY_SAMPLES {
+        let start = Instant::now();
+        if let Err(e) = redis::cmd("PING").query::<String>(con) {
+            report.fail("latency", format!("PING failed: {}", e));
+            return;
+        }
+        samples.push(start.elapsed());
+    }
+    samples.sort();
+
+    let (p50, p95, p99) = (percentile(&samples, 0.50), percentile(&samples, 0.95), percentile(&samples, 0.99));
+    let detail = format!(
+        "p50 {}, p95 {}, p99 {} over {} PINGs",
+        millis(p50),
+        millis(p95),
+        millis(p99),
+        LATENCY_SAMPLES
+    );
+    if p99 > SLOW_P99 {
+        report.warn("latency", detail);
+    } else {
+        report.pass("latency", detail);
+    }
+}
+
+/// RDB snapshots and the append only file.  CONFIG may be denied (e.g. on managed services),
+/// so the save schedule is only reported if it can be read.
+fn persistence(con: &mut Connection, info: &InfoDict, report: &mut Report) {
+    let aof = info.get::<u8>("aof_enabled").unwrap_or(0) == 1;
+    let bgsave: String = info.get("rdb_last_bgsave_status").unwrap_or_else(|| "ok".to_string());
+    let aof_write: String = info.get("aof_last_write_status").unwrap_or_else(|| "ok".to_string());
+    let save = redis::cmd("CONFIG")
+        .arg("GET")
+        .arg("save")
+        .query::<Vec<String>>(con)
+        .ok()
+        .and_then(|reply| reply.get(1).cloned());
+
+    let rdb = match &save {
+        Some(save) if save.is_empty() => "RDB off".to_string(),
+        Some(save) => format!("RDB save \"{}\"", save),
+        None => "RDB schedule unknown (CONFIG GET denied)".to_string(),
+    };
+    let detail = format!("{}, AOF {}", rdb, if aof { "on" } else { "off" });
+
+    if bgsave != "ok" || aof_write != "ok" {
+        report.fail("persistence", format!("{}; last RDB save {}, last AOF write {}", detail, bgsave, aof_write));
+    } else if !aof && save.as_deref() == Some("") {
+        report.warn("persistence", format!("{}; the streams are lost when the server restarts", detail));
+    } else {
+        report.pass("persistence", detail);
+    }
+}
+
+/// The Stream features that only newer servers have
+fn streams(con: &mut Connection, report: &mut Report) {
+    let capabilities = match rs_util::capabilities(con) {
+        Ok(capabilities) => capabilities,
+        Err(e) => {
+            report.warn("streams", format!("Could not detect the Stream features: {}", e));
+            return;
+        }
+    };
+    let missing: Vec<String> = Feature::ALL
+        .iter()
+        .filter(|feature| !capabilities.supports(**feature))
+        .map(|feature| format!("{} (Redis {})", feature, feature.since()))
+        .collect();
+    if missing.is_empty() {
+        report.pass("streams", "All Stream features are available".to_string());
+    } else {
+        report.warn("streams", format!("Not available: {}; the examples fall back or stop", missing.join(", ")));
+    }
+}
+
+/// The sample at the given fraction of the sorted samples
+fn percentile(sorted: &[Duration], fraction: f64) -> Duration {
+    let index = ((sorted.len() - 1) as f64 * fraction).round() as usize;
+    sorted[index]
+}
+
+fn millis(duration: Duration) -> String {
+    format!("{:.2} ms", duration.as_secs_f64() * 1000.0)
+}
@@ -0,0 +1,65 @@
+use redis::aio::{ConnectionLike, MultiplexedConnection};
+use redis::{ErrorKind, RedisResult, Value};
+use tokio::signal;
+use tracing::error;
+
+use crate::{client_setup_cmds, get_client, sentinel, Capabilities, Config};
+
+/// Open a multiplexed async connection to the configured server.
+/// It can be cloned cheaply and shared between tasks, but keep in mind that a blocking command,
+/// e.g. XREADGROUP with BLOCK, holds up every other command sent over the same connection.
+/// Must be called from within a tokio runtime.  A cluster is not supported.
+pub async fn get_async_connection(config: &Config) -> RedisResult<MultiplexedConnection> {
+    if config.cluster.is_some() {
+        return Err((ErrorKind::InvalidClientConfig, "A cluster needs a sync connection, from rs_util::connect or get_cluster_client").into());
+    }
+    let client = get_client(config)?;
+    let mut con = client.get_multiplexed_tokio_connection().await?;
+    if config.sentinel.is_some() {
+        sentinel::check_master_async(&mut con).await?;
+    }
+    for cmd in client_setup_cmds(config) {
+        // Best effort, just like get_connection
+        let _: RedisResult<()> = cmd.query_async(&mut con).await;
+    }
+    Ok(con)
+}
+
+/// The async twin of `rs_util::capabilities`
+pub async fn capabilities<C: ConnectionLike>(con: &mut C) -> RedisResult<Capabilities> {
+    let info: Value = redis::cmd("INFO").arg("server").query_async(con).await?;
+    let autoclaim: Option<Vec<Value>> = redis::cmd("COMMAND").arg("INFO").arg("XAUTOCLAIM").query_async(con).await.ok();
+    Capabilities::from_replies(&info, autoclaim.as_deref())
+}
+
+/// Resolves when the process receives SIGINT (Ctrl-C) or, on Unix, SIGTERM.
+/// Create it once and poll it in a `tokio::select!` loop, so no signal is missed between iterations.
+/// If a handler can't be installed, that signal is never reported, like with `shutdown_token`.
+pub async fn shutdown_signal() {
+    let ctrl_c = async {
+        if let Err(e) = signal::ctrl_c().await {
+            error!(error = %e, "Could not install the SIGINT handler; Ctrl-C stops the process right away");
+            std::future::pending::<()>().await;
+        }
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        match signal::unix::signal(signal::unix::SignalKind::terminate()) {
+            Ok(mut terminate) => {
+                terminate.recv().await;
+            }
+            Err(e) => {
+                error!(error = %e, "Could not install the SIGTERM handler");
+                std::future::pending::<()>().await;
+            }
+        }
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => {},
+        _ = terminate => {},
+    }
+}
@@ -0,0 +1,209 @@
+use std::fmt;
+
+use redis::{ConnectionLike, ErrorKind, FromRedisValue, InfoDict, RedisError, RedisResult, Value};
+
+/// A Redis server version, e.g. `7.2.4`
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Version {
+    pub major: u32,
+    pub minor: u32,
+    pub patch: u32,
+}
+
+impl Version {
+    pub const fn new(major: u32, minor: u32, patch: u32) -> Version {
+        Version { major, minor, patch }
+    }
+
+    /// Parse the `redis_version` field of INFO.  Missing or garbled parts count as 0.
+    pub fn parse(s: &str) -> Version {
+        let mut parts = s.trim().split('.').map(|part| part.parse().unwrap_or(0));
+        Version::new(parts.next().unwrap_or(0), parts.next().unwrap_or(0), parts.next().unwrap_or(0))
+    }
+}
+
+impl fmt::Display for Version {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}.{}", self.major, self.minor, self.patch)
+    }
+}
+
+/// A Stream feature that only newer servers have
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Feature {
+    /// Streams and consumer groups themselves
+    Streams,
+    /// XAUTOCLAIM
+    XAutoClaim,
+    /// The MINID trimming strategy of XADD and XTRIM
+    XAddMinId,
+    /// The `lag` and `entries-read` fields of XINFO GROUPS
+    XInfoLag,
+}
+
+impl Feature {
+    pub const ALL: [Feature; 4] = [Feature::Streams, Feature::XAutoClaim, Feature::XAddMinId, Feature::XInfoLag];
+
+    /// The first Redis version with the feature
+    pub fn since(self) -> Version {
+        match self {
+            Feature::Streams => Version::new(5, 0, 0),
+            Feature::XAutoClaim | Feature::XAddMinId => Version::new(6, 2, 0),
+            Feature::XInfoLag => Version::new(7, 0, 0),
+        }
+    }
+}
+
+impl fmt::Display for Feature {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Feature::Streams => "Streams",
+            Feature::XAutoClaim => "XAUTOCLAIM",
+            Feature::XAddMinId => "XADD/XTRIM MINID",
+            Feature::XInfoLag => "lag in XINFO GROUPS",
+        })
+    }
+}
+
+/// What the server can do, see `capabilities`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Capabilities {
+    pub version: Version,
+    features: Vec<Feature>,
+}
+
+impl Capabilities {
+    /// The features of the given Redis version
+    pub fn for_version(version: Version) -> Capabilities {
+        let features = Feature::ALL.iter().copied().filter(|feature| version >= feature.since()).collect();
+        Capabilities { version, features }
+    }
+
+    pub fn supports(&self, feature: Feature) -> bool {
+        self.features.contains(&feature)
+    }
+
+    /// The supported features, oldest first
+    pub fn features(&self) -> &[Feature] {
+        &self.features
+    }
+
+    /// An error that says which version is needed, for when there is no other way to do the job
+    pub fn require(&self, feature: Feature) -> RedisResult<()> {
+        if self.supports(feature) {
+            return Ok(());
+        }
+        Err(RedisError::from((
+            ErrorKind::ClientError,
+            "Not supported by the server",
+            format!("{} needs Redis {} or later, but the server runs {}", feature, feature.since(), self.version),
+        )))
+    }
+
+    fn without(mut self, feature: Feature) -> Capabilities {
+        self.features.retain(|f| *f != feature);
+        self
+    }
+
+    /// Build from the reply to INFO server and, if it could be asked, to COMMAND INFO XAUTOCLAIM
+    pub(crate) fn from_replies(info: &Value, autoclaim: Option<&[Value]>) -> RedisResult<Capabilities> {
+        let capabilities = Capabilities::for_version(lowest_version(info)?);
+        // COMMAND INFO answers nil for a command the server doesn't know, e.g. because it was
+        // renamed away with rename-command or the server is a fork that doesn't have it
+        match autoclaim {
+            Some(reply) if !matches!(reply.first(), Some(Value::Bulk(_))) => Ok(capabilities.without(Feature::XAutoClaim)),
+            _ => Ok(capabilities),
+        }
+    }
+}
+
+/// The version of the server, or of the oldest master of a cluster, where INFO is answered
+/// by every master as a list of `[address, info]` pairs
+fn lowest_version(info: &Value) -> RedisResult<Version> {
+    match info {
+        Value::Bulk(nodes) => {
+            let mut lowest = None;
+            for node in nodes {
+                let version = match node {
+                    Value::Bulk(pair) if pair.len() == 2 => lowest_version(&pair[1])?,
+                    _ => return Err((ErrorKind::TypeError, "Unexpected INFO reply from a cluster").into()),
+                };
+                lowest = Some(lowest.map_or(version, |lowest: Version| lowest.min(version)));
+            }
+            lowest.ok_or_else(|| (ErrorKind::TypeError, "Empty INFO reply").into())
+        }
+        value => {
+            let info = InfoDict::from_redis_value(value)?;
+            let version: String = info
+                .get("redis_version")
+                .ok_or_else(|| RedisError::from((ErrorKind::TypeError, "INFO reply without redis_version")))?;
+            Ok(Version::parse(&version))
+        }
+    }
+}
+
+/// Find out which Stream features the server supports, from its version and by asking for the
+/// commands themselves.  Works for a single server as well as for a cluster, where the oldest
+/// master decides.
+pub fn capabilities<C: ConnectionLike>(con: &mut C) -> RedisResult<Capabilities> {
+    let info: Value = redis::cmd("INFO").arg("server").query(con)?;
+    // COMMAND may be denied by an ACL, then the version has to do
+    let autoclaim: Option<Vec<Value>> = redis::cmd("COMMAND").arg("INFO").arg("XAUTOCLAIM").query(con).ok();
+    Capabilities::from_replies(&info, autoclaim.as_deref())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn info(version: &str) -> Value {
+        Value::Data(format!("# Server\r\nredis_version:{}\r\nredis_mode:standalone\r\n", version).into_bytes())
+    }
+
+    #[test]
+    fn test_version() {
+        assert_eq!(Version::parse("7.2.4"), Version::new(7, 2, 4));
+        assert_eq!(Version::parse("6.2"), Version::new(6, 2, 0));
+        assert_eq!(Version::parse("255.255.255\r"), Version::new(255, 255, 255));
+        assert!(Version::new(6, 2, 14) < Version::new(7, 0, 0));
+        assert!(Version::new(5, 0, 7) < Version::new(6, 2, 0));
+        assert_eq!(Version::new(6, 2, 0).to_string(), "6.2.0");
+    }
+
+    #[test]
+    fn test_features_by_version() {
+        let redis5 = Capabilities::for_version(Version::new(5, 0, 7));
+        assert_eq!(redis5.features(), &[Feature::Streams]);
+        let redis62 = Capabilities::for_version(Version::new(6, 2, 14));
+        assert!(redis62.supports(Feature::XAutoClaim) && redis62.supports(Feature::XAddMinId));
+        assert!(!redis62.supports(Feature::XInfoLag));
+        assert_eq!(Capabilities::for_version(Version::new(7, 2, 4)).features(), &Feature::ALL);
+        assert!(Capabilities::for_version(Version::new(4, 0, 14)).features().is_empty());
+
+        let err = redis5.require(Feature::XAutoClaim).unwrap_err();
+        assert!(err.to_string().contains("XAUTOCLAIM needs Redis 6.2.0 or later, but the server runs 5.0.7"), "{}", err);
+        assert!(redis5.require(Feature::Streams).is_ok());
+    }
+
+    #[test]
+    fn test_from_replies() {
+        let known = [Value::Bulk(vec![Value::Data(b"xautoclaim".to_vec())])];
+        let caps = Capabilities::from_replies(&info("7.0.0"), Some(&known)).unwrap();
+        assert!(caps.supports(Feature::XAutoClaim));
+        // Renamed away
+        let caps = Capabilities::from_replies(&info("7.0.0"), Some(&[Value::Nil])).unwrap();
+        assert!(!caps.supports(Feature::XAutoClaim) && caps.supports(Feature::XInfoLag));
+        // COMMAND denied
+        assert!(Capabilities::from_replies(&info("6.2.1"), None).unwrap().supports(Feature::XAutoClaim));
+
+        // A cluster with a master that wasn't upgraded yet
+        let cluster = Value::Bulk(vec![
+            Value::Bulk(vec![Value::Data(b"10.0.0.1:6379".to_vec()), info("7.2.4")]),
+            Value::Bulk(vec![Value::Data(b"10.0.0.2:6379".to_vec()), info("6.2.14")]),
+        ]);
+        let caps = Capabilities::from_replies(&cluster, None).unwrap();
+        assert_eq!(caps.version, Version::new(6, 2, 14));
+
+        assert!(Capabilities::from_replies(&Value::Data(b"# Server\r\n".to_vec()), None).is_err());
+    }
+}
@@ -0,0 +1,76 @@
+use redis::cluster::{ClusterClient, ClusterConnection};
+use redis::{Connection, ConnectionLike, ErrorKind, RedisResult, Value};
+
+use crate::{get_connection, tls_certificates, Config};
+
+/// A connection to either a single server or a cluster, whichever the configuration asks for.
+/// It implements `ConnectionLike`, so the examples use all of redis-rs' `Commands` the same way
+/// with both.
+pub enum AnyConnection {
+    Single(Connection),
+    Cluster(Box<ClusterConnection>),
+}
+
+/// Open a connection to the configured server, or to the cluster if seed nodes were given.
+/// In a cluster, every command is sent to the node that owns the slot of its key(s).
+pub fn connect(config: &Config) -> RedisResult<AnyConnection> {
+    match &config.cluster {
+        Some(_) => Ok(AnyConnection::Cluster(Box::new(get_cluster_client(config)?.get_connection()?))),
+        None => Ok(AnyConnection::Single(get_connection(config)?)),
+    }
+}
+
+/// Create a client for the configured cluster.  The seed nodes are only used to discover
+/// the cluster; all of them share the credentials and TLS settings of the configuration.
+pub fn get_cluster_client(config: &Config) -> RedisResult<ClusterClient> {
+    let seeds = match &config.cluster {
+        Some(seeds) => seeds,
+        None => return Err((ErrorKind::InvalidClientConfig, "No cluster seed nodes configured").into()),
+    };
+    let nodes = seeds
+        .iter()
+        .map(|(host, port)| Config { host: host.clone(), port: *port, cluster: None, ..config.clone() }.connection_info())
+        .collect::<RedisResult<Vec<_>>>()?;
+    let mut builder = ClusterClient::builder(nodes);
+    if let Some(certs) = config.tls.as_ref().map(tls_certificates).transpose()?.flatten() {
+        builder = builder.certs(certs);
+    }
+    builder.build()
+}
+
+impl ConnectionLike for AnyConnection {
+    fn req_packed_command(&mut self, cmd: &[u8]) -> RedisResult<Value> {
+        match self {
+            AnyConnection::Single(con) => con.req_packed_command(cmd),
+            AnyConnection::Cluster(con) => con.req_packed_command(cmd),
+        }
+    }
+
+    fn req_packed_commands(&mut self, cmd: &[u8], offset: usize, count: usize) -> RedisResult<Vec<Value>> {
+        match self {
+            AnyConnection::Single(con) => con.req_packed_commands(cmd, offset, count),
+            AnyConnection::Cluster(con) => con.req_packed_commands(cmd, offset, count),
+        }
+    }
+
+    fn get_db(&self) -> i64 {
+        match self {
+            AnyConnection::Single(con) => con.get_db(),
+            AnyConnection::Cluster(con) => con.get_db(),
+        }
+    }
+
+    fn check_connection(&mut self) -> bool {
+        match self {
+            AnyConnection::Single(con) => con.check_connection(),
+            AnyConnection::Cluster(con) => con.check_connection(),
+        }
+    }
+
+    fn is_open(&self) -> bool {
+        match self {
+            AnyConnection::Single(con) => con.is_open(),
+            AnyConnection::Cluster(con) => con.is_open(),
+        }
+    }
+}
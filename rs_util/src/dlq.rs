@@ -0,0 +1,193 @@
+//! Dead-letter streams: where consumers move the entries they can't handle, so that one bad
+//! entry doesn't stop them over and over again.  The dead-letter stream of `numbers` is
+//! `{numbers}:dlq`, in the same cluster slot.
+//!
+//! A dead letter is the entry's fields, each renamed to start with `field:`, plus fields that
+//! tell where it came from and why it was moved, all starting with `dlq-`, so that an entry can
+//! have any fields without mixing them up.  `list` reads them back and `replay` puts the original
+//! entry back on its stream, under a new ID, once whatever was wrong is fixed.
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use redis::streams::{StreamId as Entry, StreamRangeReply};
+use redis::{Cmd, Commands, ConnectionLike, Value};
+
+use crate::{keys, Context, Result};
+
+const STREAM: &str = "dlq-stream";
+const GROUP: &str = "dlq-group";
+const CONSUMER: &str = "dlq-consumer";
+const ID: &str = "dlq-id";
+const DELIVERIES: &str = "dlq-deliveries";
+const ERROR: &str = "dlq-error";
+const AT: &str = "dlq-at";
+/// What the names of the entry's own fields start with
+const FIELD: &str = "field:";
+
+/// The dead-letter stream of `stream`
+pub fn key(stream: &str) -> String {
+    keys::related(stream, "dlq")
+}
+
+/// An entry that was moved to a dead-letter stream
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct DeadLetter {
+    /// The ID of the dead letter, in the dead-letter stream
+    pub id: String,
+    /// The stream the entry was read from
+    pub stream: String,
+    pub group: String,
+    pub consumer: String,
+    /// The ID the entry had in `stream`
+    pub entry_id: String,
+    /// How many times the entry had been delivered to the group
+    pub deliveries: usize,
+    /// Why it was moved
+    pub error: String,
+    /// When it was moved, in milliseconds since the epoch
+    pub at: u64,
+    /// The fields of the entry, sorted by name
+    pub fields: Vec<(String, Vec<u8>)>,
+}
+
+impl DeadLetter {
+    /// The dead letter for an entry that `consumer` of `group` couldn't handle
+    pub fn new(stream: &str, group: &str, consumer: &str, entry: &Entry, deliveries: usize, error: &str) -> DeadLetter {
+        DeadLetter {
+            id: String::new(),
+            stream: stream.to_string(),
+            group: group.to_string(),
+            consumer: consumer.to_string(),
+            entry_id: entry.id.clone(),
+            deliveries,
+            error: error.to_string(),
+            at: SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_millis() as u64),
+            fields: fields(entry),
+        }
+    }
+
+    /// Read a dead letter back from its entry in the dead-letter stream
+    fn from_entry(entry: &Entry) -> DeadLetter {
+        let text = |field: &str| entry.get::<String>(field).unwrap_or_default();
+        DeadLetter {
+            id: entry.id.clone(),
+            stream: text(STREAM),
+            group: text(GROUP),
+            consumer: text(CONSUMER),
+            entry_id: text(ID),
+            deliveries: entry.get(DELIVERIES).unwrap_or_default(),
+            error: text(ERROR),
+            at: entry.get(AT).unwrap_or_default(),
+            fields: fields(entry)
+                .into_iter()
+                .filter_map(|(field, value)| Some((field.strip_prefix(FIELD)?.to_string(), value)))
+                .collect(),
+        }
+    }
+
+    /// The XADD that moves the entry to the dead-letter stream of its stream, for sync and
+    /// async connections alike
+    pub fn add_cmd(&self) -> Cmd {
+        let mut cmd = redis::cmd("XADD");
+        cmd.arg(key(&self.stream))
+            .arg("*")
+            .arg(STREAM)
+            .arg(&self.stream)
+            .arg(GROUP)
+            .arg(&self.group)
+            .arg(CONSUMER)
+            .arg(&self.consumer)
+            .arg(ID)
+            .arg(&self.entry_id)
+            .arg(DELIVERIES)
+            .arg(self.deliveries)
+            .arg(ERROR)
+            .arg(&self.error)
+            .arg(AT)
+            .arg(self.at);
+        for (field, value) in &self.fields {
+            cmd.arg(format!("{}{}", FIELD, field)).arg(value);
+        }
+        cmd
+    }
+}
+
+/// The fields of an entry, sorted by name
+fn fields(entry: &Entry) -> Vec<(String, Vec<u8>)> {
+    let mut fields: Vec<(String, Vec<u8>)> = entry
+        .map
+        .iter()
+        .filter_map(|(field, value)| match value {
+            Value::Data(bytes) => Some((field.clone(), bytes.clone())),
+            _ => None,
+        })
+        .collect();
+    fields.sort();
+    fields
+}
+
+/// The first `count` dead letters of `stream`, oldest first
+pub fn list<C: ConnectionLike>(con: &mut C, stream: &str, count: usize) -> Result<Vec<DeadLetter>> {
+    let reply: StreamRangeReply = con
+        .xrange_count(key(stream), "-", "+", count)
+        .with_context(|| format!("reading the dead letters of {}", stream))?;
+    Ok(reply.ids.iter().map(DeadLetter::from_entry).collect())
+}
+
+/// Add the original entry back to its stream, where the consumers read it like a new one, and
+/// delete the dead letter.  Returns the new ID of the entry.
+pub fn replay<C: ConnectionLike>(con: &mut C, letter: &DeadLetter) -> Result<String> {
+    let id: String = con
+        .xadd(&letter.stream, "*", &letter.fields)
+        .with_context(|| format!("replaying dead letter {} to {}", letter.id, letter.stream))?;
+    let _: usize = con
+        .xdel(key(&letter.stream), &[&letter.id])
+        .with_context(|| format!("deleting dead letter {}", letter.id))?;
+    Ok(id)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+
+    #[test]
+    fn test_round_trip() {
+        let entry = Entry {
+            id: "1-0".to_string(),
+            map: HashMap::from([
+                ("n".to_string(), Value::Data(b"one".to_vec())),
+                // Fields named like the metadata stay the entry's own
+                ("dlq-reason".to_string(), Value::Data(b"mine".to_vec())),
+                ("dlq-error".to_string(), Value::Data(b"also mine".to_vec())),
+            ]),
+        };
+        let letter = DeadLetter::new("numbers", "primes", "BOB-01", &entry, 3, "not a number");
+
+        // What XADD would store, as XRANGE answers it
+        let args: Vec<Vec<u8>> = letter
+            .add_cmd()
+            .args_iter()
+            .map(|arg| match arg {
+                redis::Arg::Simple(bytes) => bytes.to_vec(),
+                redis::Arg::Cursor => vec![],
+            })
+            .collect();
+        assert_eq!(args[1], b"{numbers}:dlq");
+        let map = args[3..].chunks(2).map(|pair| (String::from_utf8(pair[0].clone()).unwrap(), Value::Data(pair[1].clone())));
+        let stored = Entry { id: "5-0".to_string(), map: map.collect() };
+
+        let read = DeadLetter::from_entry(&stored);
+        assert_eq!(read, DeadLetter { id: "5-0".to_string(), ..letter });
+        assert_eq!(read.error, "not a number");
+        assert_eq!(
+            read.fields,
+            vec![
+                ("dlq-error".to_string(), b"also mine".to_vec()),
+                ("dlq-reason".to_string(), b"mine".to_vec()),
+                ("n".to_string(), b"one".to_vec()),
+            ]
+        );
+    }
+}
@@ -0,0 +1,236 @@
+use std::error;
+use std::fmt;
+use std::io;
+use std::process::ExitCode;
+
+use redis::streams::StreamId as Entry;
+use redis::{ErrorKind, FromRedisValue, RedisError};
+
+use crate::{ConfigError, ParseStreamIdError, PoolError};
+
+/// Everything that can go wrong in the examples, grouped by what the user can do about it.
+/// Each group ends the process with its own exit code, see `Error::exit_code`.
+#[derive(Debug)]
+pub enum Error {
+    /// The connection settings are invalid
+    Config(ConfigError),
+    /// The server can't be reached, or the connection broke
+    Connection(RedisError),
+    /// The server rejected the credentials, or the user lacks a permission
+    Auth(RedisError),
+    /// The server answered with an error, e.g. an unknown command or a wrong type
+    Protocol(RedisError),
+    /// A reply or a field could not be converted to the expected type
+    Decode(RedisError),
+    /// A stream entry lacks a field the example needs
+    MissingField { id: String, field: String },
+    /// Reading or writing a local file, or the terminal
+    Io(io::Error),
+    /// Anything else, e.g. a diagnostic check that failed
+    Other(String),
+    /// What was being done when the error happened, see `Context`
+    Context { context: String, source: Box<Error> },
+}
+
+/// The result of everything in rs_util and the examples that can fail
+pub type Result<T> = std::result::Result<T, Error>;
+
+impl Error {
+    /// The process exit code: 2 for bad settings, like invalid arguments, and one code per
+    /// group after that, so scripts can tell a server that is down from a bad password
+    pub fn exit_code(&self) -> u8 {
+        match self {
+            Error::Other(_) => 1,
+            Error::Config(_) => 2,
+            Error::Connection(_) => 3,
+            Error::Auth(_) => 4,
+            Error::Protocol(_) => 5,
+            Error::Decode(_) => 6,
+            Error::MissingField { .. } => 7,
+            Error::Io(_) => 8,
+            Error::Context { source, .. } => source.exit_code(),
+        }
+    }
+
+    /// The innermost error, below all the context
+    pub fn root(&self) -> &Error {
+        match self {
+            Error::Context { source, .. } => source.root(),
+            e => e,
+        }
+    }
+
+    pub fn context<C: Into<String>>(self, context: C) -> Error {
+        Error::Context { context: context.into(), source: Box::new(self) }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Config(e) => write!(f, "invalid settings: {}", e),
+            Error::Connection(e) => write!(f, "connection failed: {}", e),
+            Error::Auth(e) => write!(f, "not authorized: {}", e),
+            Error::Protocol(e) => write!(f, "server error: {}", e),
+            Error::Decode(e) => write!(f, "unexpected value: {}", e),
+            Error::MissingField { id, field } => write!(f, "entry {} has no field '{}'", id, field),
+            Error::Io(e) => write!(f, "{}", e),
+            Error::Other(message) => f.write_str(message),
+            Error::Context { context, source } => write!(f, "{}: {}", context, source),
+        }
+    }
+}
+
+impl error::Error for Error {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            Error::Config(e) => Some(e),
+            Error::Connection(e) | Error::Auth(e) | Error::Protocol(e) | Error::Decode(e) => Some(e),
+            Error::Io(e) => Some(e),
+            Error::Context { source, .. } => Some(source.as_ref()),
+            Error::MissingField { .. } | Error::Other(_) => None,
+        }
+    }
+}
+
+impl From<RedisError> for Error {
+    fn from(e: RedisError) -> Error {
+        if e.is_connection_dropped() || e.is_connection_refusal() || e.is_timeout() || e.is_io_error() {
+            return Error::Connection(e);
+        }
+        match (e.kind(), e.code()) {
+            (ErrorKind::AuthenticationFailed, _) | (_, Some("NOAUTH" | "WRONGPASS" | "NOPERM")) => Error::Auth(e),
+            (ErrorKind::TypeError, _) => Error::Decode(e),
+            (ErrorKind::InvalidClientConfig, _) => Error::Config(ConfigError::from(e.to_string())),
+            (ErrorKind::MasterNameNotFoundBySentinel | ErrorKind::EmptySentinelList | ErrorKind::ClusterConnectionNotFound, _) => {
+                Error::Connection(e)
+            }
+            _ => Error::Protocol(e),
+        }
+    }
+}
+
+impl From<ConfigError> for Error {
+    fn from(e: ConfigError) -> Error {
+        Error::Config(e)
+    }
+}
+
+impl From<PoolError> for Error {
+    fn from(e: PoolError) -> Error {
+        match e {
+            PoolError::Redis(e) => e.into(),
+            PoolError::Pool(e) => Error::Connection((ErrorKind::IoError, "Could not fill the connection pool", e.to_string()).into()),
+        }
+    }
+}
+
+impl From<r2d2::Error> for Error {
+    fn from(e: r2d2::Error) -> Error {
+        Error::Connection((ErrorKind::IoError, "No connection available from the pool", e.to_string()).into())
+    }
+}
+
+impl From<ParseStreamIdError> for Error {
+    fn from(e: ParseStreamIdError) -> Error {
+        Error::Decode((ErrorKind::TypeError, "Invalid stream ID", e.to_string()).into())
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(e: io::Error) -> Error {
+        Error::Io(e)
+    }
+}
+
+/// Add what was being done to an error, e.g.
+/// `con.xrange(key, "-", "+").context(format!("reading {}", key))?`
+pub trait Context<T> {
+    fn context<C: Into<String>>(self, context: C) -> Result<T>;
+
+    /// Like `context`, but only builds the message if there is an error
+    fn with_context<C: Into<String>, F: FnOnce() -> C>(self, f: F) -> Result<T>;
+}
+
+impl<T, E: Into<Error>> Context<T> for std::result::Result<T, E> {
+    fn context<C: Into<String>>(self, context: C) -> Result<T> {
+        self.map_err(|e| e.into().context(context))
+    }
+
+    fn with_context<C: Into<String>, F: FnOnce() -> C>(self, f: F) -> Result<T> {
+        self.map_err(|e| e.into().context(f()))
+    }
+}
+
+/// A field of a stream entry, telling a missing field apart from one that is not a `T`
+pub fn entry_field<T: FromRedisValue>(entry: &Entry, field: &str) -> Result<T> {
+    let value = entry.map.get(field).ok_or_else(|| Error::MissingField {
+        id: entry.id.clone(),
+        field: field.to_string(),
+    })?;
+    T::from_redis_value(value).map_err(|e| Error::Decode(e).context(format!("field '{}' of entry {}", field, entry.id)))
+}
+
+/// End `main` with the result of an example: nothing on success, otherwise one line on stderr
+/// and the error's exit code, e.g. `fn main() -> ExitCode { rs_util::exit_code(run(&config)) }`
+pub fn exit_code(result: Result<()>) -> ExitCode {
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("[ERROR] {}", e);
+            ExitCode::from(e.exit_code())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use redis::Value;
+
+    use super::*;
+
+    #[test]
+    fn test_classify() {
+        let refused: RedisError = io::Error::new(io::ErrorKind::ConnectionRefused, "refused").into();
+        assert!(matches!(Error::from(refused), Error::Connection(_)));
+        let wrongpass = redis::parse_redis_value(b"-WRONGPASS invalid username-password pair\r\n").unwrap_err();
+        assert!(matches!(Error::from(wrongpass), Error::Auth(_)));
+        let auth = RedisError::from((ErrorKind::AuthenticationFailed, "Password authentication failed"));
+        assert_eq!(Error::from(auth).exit_code(), 4);
+        let wrongtype = RedisError::from((ErrorKind::ResponseError, "WRONGTYPE"));
+        assert!(matches!(Error::from(wrongtype), Error::Protocol(_)));
+        let decode = RedisError::from((ErrorKind::TypeError, "Response was of incompatible type"));
+        assert_eq!(Error::from(decode).exit_code(), 6);
+    }
+
+    #[test]
+    fn test_context_chain() {
+        let result: std::result::Result<(), ConfigError> = Err(ConfigError::from("--port: not a number".to_string()));
+        let e = result.context("reading the settings").context("starting producer_1").unwrap_err();
+        assert_eq!(e.to_string(), "starting producer_1: reading the settings: invalid settings: --port: not a number");
+        assert_eq!(e.exit_code(), 2);
+        assert!(matches!(e.root(), Error::Config(_)));
+    }
+
+    #[test]
+    fn test_entry_field() {
+        let entry = Entry {
+            id: "1-0".to_string(),
+            map: HashMap::from([
+                ("n".to_string(), Value::Data(b"42".to_vec())),
+                ("temp".to_string(), Value::Data(b"warm".to_vec())),
+            ]),
+        };
+        assert_eq!(entry_field::<i64>(&entry, "n").unwrap(), 42);
+
+        let missing = entry_field::<i64>(&entry, "postal_code").unwrap_err();
+        assert_eq!(missing.to_string(), "entry 1-0 has no field 'postal_code'");
+        assert_eq!(missing.exit_code(), 7);
+
+        let garbled = entry_field::<i32>(&entry, "temp").unwrap_err();
+        assert!(garbled.to_string().starts_with("field 'temp' of entry 1-0: unexpected value"), "{}", garbled);
+        assert!(matches!(garbled.root(), Error::Decode(_)));
+    }
+}
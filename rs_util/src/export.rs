@@ -0,0 +1,191 @@
+//! Stream entries as files, to move them between servers or keep them as test fixtures.
+//!
+//! NDJSON has one entry per line, `{"id":"1-0","fields":{"n":"1"}}`.  CSV has one entry per
+//! row, without a header: the ID, then each field name followed by its value, e.g.
+//! `1-0,n,1`, so entries with different fields can share a file.  The fields are sorted by
+//! name in both formats, and their values must be UTF-8.
+
+use std::collections::BTreeMap;
+use std::io::{self, BufRead, Write};
+
+use redis::streams::StreamId as Entry;
+use serde::{Deserialize, Serialize};
+
+use crate::{entry_field, Error, Result};
+
+/// The file format of exported entries
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Format {
+    /// One JSON object per line
+    Ndjson,
+    /// One row per entry: the ID, then field, value, field, value...
+    Csv,
+}
+
+impl Format {
+    /// The format called `name`, `ndjson` or `csv`
+    pub fn from_name(name: &str) -> Option<Format> {
+        match name {
+            "ndjson" => Some(Format::Ndjson),
+            "csv" => Some(Format::Csv),
+            _ => None,
+        }
+    }
+}
+
+/// An exported entry
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Record {
+    pub id: String,
+    pub fields: BTreeMap<String, String>,
+}
+
+impl Record {
+    /// The record of an entry read from a stream.  A value that isn't UTF-8 is an error.
+    pub fn from_entry(entry: &Entry) -> Result<Record> {
+        let fields = entry
+            .map
+            .keys()
+            .map(|field| Ok((field.clone(), entry_field::<String>(entry, field)?)))
+            .collect::<Result<_>>()?;
+        Ok(Record { id: entry.id.clone(), fields })
+    }
+}
+
+/// Writes records in a format
+pub struct Writer<W: Write> {
+    out: Out<W>,
+}
+
+enum Out<W: Write> {
+    Ndjson(W),
+    Csv(Box<csv::Writer<W>>),
+}
+
+impl<W: Write> Writer<W> {
+    pub fn new(out: W, format: Format) -> Writer<W> {
+        let out = match format {
+            Format::Ndjson => Out::Ndjson(out),
+            Format::Csv => Out::Csv(Box::new(csv::WriterBuilder::new().has_headers(false).flexible(true).from_writer(out))),
+        };
+        Writer { out }
+    }
+
+    pub fn write(&mut self, record: &Record) -> Result<()> {
+        match &mut self.out {
+            Out::Ndjson(out) => {
+                serde_json::to_writer(&mut *out, record).map_err(io::Error::from)?;
+                out.write_all(b"\n")?;
+            }
+            Out::Csv(out) => {
+                let mut row = vec![record.id.as_str()];
+                for (field, value) in &record.fields {
+                    row.push(field);
+                    row.push(value);
+                }
+                out.write_record(&row).map_err(io::Error::from)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Write out what is buffered, e.g. after each page, so that a stopped export can resume
+    /// right after the last record in the file
+    pub fn flush(&mut self) -> Result<()> {
+        match &mut self.out {
+            Out::Ndjson(out) => out.flush()?,
+            Out::Csv(out) => out.flush()?,
+        }
+        Ok(())
+    }
+
+    /// Flush, and give back what was written to
+    pub fn into_inner(self) -> Result<W> {
+        match self.out {
+            Out::Ndjson(mut out) => {
+                out.flush()?;
+                Ok(out)
+            }
+            Out::Csv(out) => out.into_inner().map_err(|e| Error::Io(e.into_error())),
+        }
+    }
+}
+
+/// The records of a file, in a format
+pub fn read<'a, R: BufRead + 'a>(input: R, format: Format) -> Box<dyn Iterator<Item = Result<Record>> + 'a> {
+    match format {
+        Format::Ndjson => Box::new(
+            input
+                .lines()
+                .enumerate()
+                .filter(|(_, line)| !matches!(line, Ok(line) if line.trim().is_empty()))
+                .map(|(n, line)| {
+                    serde_json::from_str(&line?).map_err(|e| Error::Other(format!("line {}: {}", n + 1, e)))
+                }),
+        ),
+        Format::Csv => Box::new(
+            csv::ReaderBuilder::new()
+                .has_headers(false)
+                .flexible(true)
+                .from_reader(input)
+                .into_records()
+                .enumerate()
+                .map(|(n, row)| {
+                    let row = row.map_err(|e| Error::Other(format!("row {}: {}", n + 1, e)))?;
+                    if row.len() % 2 == 0 {
+                        return Err(Error::Other(format!("row {}: a field without a value", n + 1)));
+                    }
+                    let fields = row.iter().skip(1).collect::<Vec<_>>();
+                    Ok(Record {
+                        id: row[0].to_string(),
+                        fields: fields.chunks(2).map(|pair| (pair[0].to_string(), pair[1].to_string())).collect(),
+                    })
+                }),
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use redis::Value;
+
+    use super::*;
+
+    fn record(id: &str, fields: &[(&str, &str)]) -> Record {
+        Record {
+            id: id.to_string(),
+            fields: fields.iter().map(|(field, value)| (field.to_string(), value.to_string())).collect(),
+        }
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let records = vec![record("1-0", &[("n", "1")]), record("2-0", &[("a", "x, \"y\""), ("b", "line\nbreak")]), record("3-0", &[])];
+        for format in [Format::Ndjson, Format::Csv] {
+            let mut writer = Writer::new(vec![], format);
+            for record in &records {
+                writer.write(record).unwrap();
+            }
+            let file = writer.into_inner().unwrap();
+            let read: Vec<Record> = read(&file[..], format).collect::<Result<_>>().unwrap();
+            assert_eq!(read, records, "{:?}", format);
+        }
+
+        assert!(read(&b"1-0,n\n"[..], Format::Csv).next().unwrap().is_err());
+        assert_eq!(read(&b"1-0,n,1\n"[..], Format::Csv).next().unwrap().unwrap(), record("1-0", &[("n", "1")]));
+    }
+
+    #[test]
+    fn test_from_entry() {
+        let entry = Entry {
+            id: "1-0".to_string(),
+            map: HashMap::from([("b".to_string(), Value::Data(b"2".to_vec())), ("a".to_string(), Value::Data(b"1".to_vec()))]),
+        };
+        assert_eq!(Record::from_entry(&entry).unwrap(), record("1-0", &[("a", "1"), ("b", "2")]));
+
+        let binary = Entry { id: "2-0".to_string(), map: HashMap::from([("b".to_string(), Value::Data(vec![0xff]))]) };
+        assert!(Record::from_entry(&binary).is_err());
+    }
+}
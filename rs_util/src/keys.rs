@@ -0,0 +1,57 @@
+//! Key naming that works the same on a single server and in a cluster.
+//!
+//! A cluster only runs a multi-key command, a transaction or a Lua script when all of its keys
+//! hash to the same slot.  When a key contains a hash tag, `{...}`, only the tag is hashed,
+//! so every key that shares the tag of a Stream lives in the Stream's slot.
+//! A Stream on its own needs no tag, so the labs keep their keys, e.g. `numbers`: only the keys
+//! kept alongside one, like its dead-letter stream, get a tag, see `related`.
+//! Consumer group and consumer names are not keys and need no tag.
+
+/// A key kept alongside `key`, in the same slot: `related("numbers", "dlq")` is
+/// `{numbers}:dlq`, the whole key becoming the tag.  A key with a hash tag keeps it, e.g.
+/// `related("stream:{numbers}", "dlq")` is `stream:{numbers}:dlq`.
+pub fn related(key: &str, suffix: &str) -> String {
+    match hash_tag(key) {
+        Some(_) => format!("{}:{}", key, suffix),
+        None => format!("{{{}}}:{}", key, suffix),
+    }
+}
+
+/// The hash tag of a key, i.e. the part between the first `{` and the next `}`, if it isn't empty
+pub fn hash_tag(key: &str) -> Option<&str> {
+    let start = key.find('{')? + 1;
+    let len = key[start..].find('}')?;
+    if len == 0 {
+        None
+    } else {
+        Some(&key[start..start + len])
+    }
+}
+
+/// The cluster slot of a key
+pub fn slot(key: &str) -> u16 {
+    redis::cluster_routing::get_slot(key.as_bytes())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hash_tag() {
+        assert_eq!(hash_tag("stream:{numbers}"), Some("numbers"));
+        assert_eq!(hash_tag("{a}{b}"), Some("a"));
+        assert_eq!(hash_tag("a{}b{c}"), None);
+        assert_eq!(hash_tag("a{b"), None);
+        assert_eq!(hash_tag("numbers"), None);
+    }
+
+    #[test]
+    fn test_related_keys_share_a_slot() {
+        assert_eq!(related("stream:{numbers}", "dlq"), "stream:{numbers}:dlq");
+        assert_eq!(slot(&related("stream:{numbers}", "dlq")), slot("stream:{numbers}"));
+        assert_eq!(related("numbers", "dlq"), "{numbers}:dlq");
+        assert_eq!(slot(&related("numbers", "dlq")), slot("numbers"));
+        assert_eq!(slot(&related("stream:weather", "dlq")), slot("stream:weather"));
+    }
+}
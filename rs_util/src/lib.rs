@@ -1,145 +1,142 @@
-use clap_v3::{App, Arg};
-use redis::{Connection, ConnectionInfo, RedisResult};
-
-#[derive(Clone, Debug)]
-pub struct Config {
-    pub host: String,
-    pub port: u16,
-    pub db: i64,
-    pub username: Option<String>,
-    pub password: Option<String>
-}
-
+use std::fs;
+use std::net::SocketAddr;
+use std::process;
+
+use clap_v3::ArgMatches;
+use redis::{ClientTlsConfig, Connection, ErrorKind, RedisResult, TlsCertificates};
+
+pub mod aio;
+mod capabilities;
+mod cluster;
+mod config;
+pub mod dlq;
+mod error;
+pub mod export;
+pub mod keys;
+mod logging;
+mod metrics;
+mod pacing;
+mod pool;
+mod range;
+mod resilient;
+mod retention;
+pub mod sentinel;
+mod shutdown;
+mod stream_id;
+pub mod streams;
+mod time_range;
+pub mod window;
+
+pub use aio::get_async_connection;
+pub use capabilities::{capabilities, Capabilities, Feature, Version};
+pub use cluster::{connect, get_cluster_client, AnyConnection};
+pub use config::{app, config_from_matches, Config, ConfigError, SentinelConfig, Settings, TlsConfig};
+pub use error::{entry_field, exit_code, Context, Error, Result};
+pub use logging::{init_logging, log_config, LogConfig, LogFormat};
+pub use metrics::{metrics, serve_metrics, Metrics};
+pub use pacing::{parse_speed, Pacing};
+pub use pool::{pool, pool_with_options, ConnectionManager, Pool, PoolError, PoolOptions, PooledConnection};
+pub use range::RangePages;
+pub use resilient::{Backoff, ReconnectEvent, ResilientConnection, Retry, WithRetry};
+pub use retention::{parse_duration, retention_args, Limit, Retention};
+pub use shutdown::{shutdown_token, terminate, Shutdown};
+pub use stream_id::{EntryId, ParseStreamIdError, StreamId};
+pub use time_range::{parse_time, time_range_args, TimeRange};
+
+/// Parse the command line and resolve the connection configuration.
+/// Invalid settings end the process with a message, just like invalid arguments do.
 pub fn app_config (name: String, about: String) -> Config {
-    let matches = App::new(name)
-    .about(&about[..])
-    .version("0.1.0")
-    .arg(
-        Arg::with_name("HOST")
-            .help("The resolvable hostname or IP address of the Redis server")
-            .long("host")
-            .short('h')
-            .default_value("127.0.0.1")
-    )
-    .arg(
-        Arg::with_name("PORT")
-            .help("TCP port number of the Redis server")
-            .long("port")
-            .short('p')
-            .default_value("6379")
-    )
-    .arg(
-        Arg::with_name("DB")
-        .help("DB number")
-        .long("db")
-        .short('d')
-        .default_value("0")
-    )
-    .arg(
-        Arg::with_name("USERNAME")
-        .help("Username for the connection")
-        .long("username")
-        .short('u')
-    )
-    .arg(
-        Arg::with_name("PASSWORD")
-        .help("Password for connection")
-        .long("password")
-        .short('w')
-    )
-    .get_matches();
-
-    Config {
-        host: matches.value_of("HOST").unwrap().to_string(),
-        port: matches.value_of("PORT").unwrap().parse().unwrap_or(6379),
-        db: matches.value_of("DB").unwrap().parse().unwrap_or(0),
-        username: match matches.value_of("USERNAME") {
-            Some(val) => Some(val.to_string()),
-            None => None
-        },
-        password: match matches.value_of("PASSWORD") {
-            Some(val) => Some(val.to_string()),
-            None => None
-        }
-    }
+    let matches = app(&name, &about).get_matches();
+    config_or_exit(&matches)
 }
 
-pub fn get_connection (config: &Config) -> RedisResult<Connection> {
-    let con_info = ConnectionInfo {
-        addr: redis::ConnectionAddr::Tcp(config.host.clone(), config.port),
-        redis: redis::RedisConnectionInfo {
-            db: config.db,
-            username: config.username.clone(),
-            password: config.password.clone(),
+/// Set up logging, resolve the connection configuration from the matches of an `app`
+/// with extra arguments and start serving the metrics if `--metrics-addr` asks for it,
+/// ending the process with a message if the settings are invalid
+pub fn config_or_exit (matches: &ArgMatches) -> Config {
+    init_logging(&LogConfig::from_matches(matches));
+    let result = config_from_matches(matches).map_err(Error::from).and_then(|config| {
+        if let Some(addr) = matches.value_of("METRICS_ADDR") {
+            let addr: SocketAddr = addr
+                .parse()
+                .map_err(|_| ConfigError::from(format!("--metrics-addr: '{}' is not an address and port", addr)))?;
+            serve_metrics(addr, &config).with_context(|| format!("serving metrics at {}", addr))?;
         }
-    };
-
-    // Open a connection to the Redis server with the default info or what was provided on the command line
-    let client = redis::Client::open(con_info)?;
-    client.get_connection()
+        Ok(config)
+    });
+    match result {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!("[ERROR] {}", e);
+            process::exit(e.exit_code().into());
+        }
+    }
 }
 
-const MAX_SEQ: i64 = 2^64 -1;
+/// Create a client for the configured server, loading any TLS certificates.
+/// With Sentinels configured, the client is for wherever the master is right now.
+/// A cluster needs `get_cluster_client` instead.
+pub fn get_client (config: &Config) -> RedisResult<redis::Client> {
+    if config.cluster.is_some() {
+        return Err((ErrorKind::InvalidClientConfig, "A cluster needs rs_util::connect or get_cluster_client").into());
+    }
+    if let Some(sentinel) = &config.sentinel {
+        let (host, port) = sentinel::master_addr(sentinel)?;
+        return get_client(&Config { host, port, sentinel: None, ..config.clone() });
+    }
 
-/// Increment a Stream message ID by one
-pub fn incr_id(id: &str) -> String {
-    let parts: Vec<i64> = id.split('-').map(|part| part.parse::<i64>()
-        .expect("[ERROR] Could not parse stream entry id!")).collect();
-    let mut time = parts[0];
-    let mut seq = parts[1];
+    let con_info = config.connection_info()?;
 
-    if seq == MAX_SEQ {
-        time +=1;
-        seq = 0;
-    } else {
-        seq += 1;
+    match &config.tls {
+        Some(tls) => match tls_certificates(tls)? {
+            Some(certs) => redis::Client::build_with_tls(con_info, certs),
+            None => redis::Client::open(con_info),
+        },
+        None => redis::Client::open(con_info),
     }
-
-    format!("{}-{}", time, seq)
 }
 
-/// Decrement a Stream message ID by one
-pub fn decr_id(id: &str) -> String {
-    let parts: Vec<i64> = id.split('-').map(|part| part.parse::<i64>()
-        .expect("[ERROR] Could not parse stream entry id!")).collect();
-    let mut time = parts[0];
-    let mut seq = parts[1];
-
-    if seq == 0 {
-        time -=1;
-        seq = MAX_SEQ;
-    } else {
-        seq -= 1;
+/// Load the CA and client certificates, if any were configured
+pub(crate) fn tls_certificates(tls: &TlsConfig) -> RedisResult<Option<TlsCertificates>> {
+    if tls.ca_file.is_none() && tls.cert_file.is_none() {
+        return Ok(None);
     }
-
-    format!("{}-{}", time, seq)
+    let client_tls = match (&tls.cert_file, &tls.key_file) {
+        (Some(cert_file), Some(key_file)) => Some(ClientTlsConfig {
+            client_cert: fs::read(cert_file)?,
+            client_key: fs::read(key_file)?,
+        }),
+        _ => None,
+    };
+    let root_cert = match &tls.ca_file {
+        Some(ca_file) => Some(fs::read(ca_file)?),
+        None => None,
+    };
+    Ok(Some(TlsCertificates { client_tls, root_cert }))
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-
-    #[test]
-    fn test_incr_id() {
-        let id = "1643414204175-0";
-        assert_eq!(incr_id(id), "1643414204175-1");
+pub fn get_connection (config: &Config) -> RedisResult<Connection> {
+    // Open a connection to the Redis server with the default info or what was provided on the command line
+    let client = get_client(config)?;
+    let mut con = client.get_connection()?;
+    if config.sentinel.is_some() {
+        sentinel::check_master(&mut con)?;
     }
-
-    #[test]
-    fn test_incr_id_max() {
-        let id = format!("1643414204175-{}", 2^64 - 1);
-        assert_eq!(incr_id(&id), "1643414204176-0")
+    for cmd in client_setup_cmds(config) {
+        // Naming is best effort: older servers don't know CLIENT SETINFO, and an ACL may deny CLIENT
+        let _: RedisResult<()> = cmd.query(&mut con);
     }
+    Ok(con)
+}
 
-    #[test]
-    fn test_decr_id() {
-        let id = format!("1643414204175-23");
-        assert_eq!(decr_id(&id), "1643414204175-22");
+/// The CLIENT SETNAME and CLIENT SETINFO commands that identify a connection in CLIENT LIST
+pub(crate) fn client_setup_cmds(config: &Config) -> Vec<redis::Cmd> {
+    let mut cmds = vec![redis::cmd("CLIENT").arg("SETNAME").arg(&config.client_name).clone()];
+    if let Some(lib_name) = &config.lib_name {
+        cmds.push(redis::cmd("CLIENT").arg("SETINFO").arg("LIB-NAME").arg(lib_name).clone());
     }
-
-    #[test]
-    fn test_decr_id_zero() {
-        let id = format!("1643414204175-0");
-        assert_eq!(decr_id(&id), format!("1643414204174-{}", 2^64 - 1));
+    if let Some(lib_ver) = &config.lib_ver {
+        cmds.push(redis::cmd("CLIENT").arg("SETINFO").arg("LIB-VER").arg(lib_ver).clone());
     }
-}
\ No newline at end of file
+    cmds
+}
@@ -0,0 +1,111 @@
+use std::sync::OnceLock;
+
+use clap_v3::ArgMatches;
+use tracing::Level;
+use tracing_subscriber::EnvFilter;
+
+/// How log events are written to stderr
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LogFormat {
+    /// One human readable line per event, with a timestamp, the level and the spans
+    Text,
+    /// One JSON object per event, for log pipelines
+    Json,
+}
+
+/// The logging settings, from `-v`/`-q` and `--log-format`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LogConfig {
+    pub level: Level,
+    pub format: LogFormat,
+}
+
+impl Default for LogConfig {
+    fn default() -> LogConfig {
+        LogConfig { level: Level::INFO, format: LogFormat::Text }
+    }
+}
+
+impl LogConfig {
+    /// Every `-v` lowers the level by one step from INFO, every `-q` raises it
+    pub fn from_matches(matches: &ArgMatches) -> LogConfig {
+        let verbosity = matches.occurrences_of("VERBOSE") as i64 - matches.occurrences_of("QUIET") as i64;
+        let level = match verbosity {
+            i64::MIN..=-2 => Level::ERROR,
+            -1 => Level::WARN,
+            0 => Level::INFO,
+            1 => Level::DEBUG,
+            _ => Level::TRACE,
+        };
+        let format = match matches.value_of("LOG_FORMAT") {
+            Some("json") => LogFormat::Json,
+            _ => LogFormat::Text,
+        };
+        LogConfig { level, format }
+    }
+
+    /// The command line arguments for the same settings, e.g. to start a child process that
+    /// logs just like this one
+    pub fn to_args(&self) -> Vec<String> {
+        let flag = match self.level {
+            Level::ERROR => Some("-qq"),
+            Level::WARN => Some("-q"),
+            Level::INFO => None,
+            Level::DEBUG => Some("-v"),
+            Level::TRACE => Some("-vv"),
+        };
+        let mut args: Vec<String> = flag.into_iter().map(String::from).collect();
+        if self.format == LogFormat::Json {
+            args.extend(["--log-format".to_string(), "json".to_string()]);
+        }
+        args
+    }
+}
+
+static LOG_CONFIG: OnceLock<LogConfig> = OnceLock::new();
+
+/// Install the global tracing subscriber.  Only the first call has an effect.
+/// `RUST_LOG`, if set, takes precedence over the level, e.g. `RUST_LOG=rrbe=debug,info`.
+/// The events go to stderr, which leaves stdout to the output of an example, e.g. an export.
+pub fn init_logging(log: &LogConfig) {
+    if LOG_CONFIG.set(log.clone()).is_err() {
+        return;
+    }
+    let filter = EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new(log.level.as_str()));
+    let builder = tracing_subscriber::fmt().with_env_filter(filter).with_writer(std::io::stderr);
+    // Another subscriber may already be installed, e.g. by a test harness; then that one is used
+    let _ = match log.format {
+        LogFormat::Text => builder.try_init(),
+        LogFormat::Json => builder.json().flatten_event(true).with_span_list(true).try_init(),
+    };
+}
+
+/// The settings `init_logging` was called with, or the defaults
+pub fn log_config() -> LogConfig {
+    LOG_CONFIG.get().cloned().unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app;
+
+    fn log(args: &[&str]) -> LogConfig {
+        let matches = app("test", "test").get_matches_from(std::iter::once("test").chain(args.iter().copied()));
+        LogConfig::from_matches(&matches)
+    }
+
+    #[test]
+    fn test_levels_and_args() {
+        assert_eq!(log(&[]), LogConfig::default());
+        assert_eq!(log(&["-v"]).level, Level::DEBUG);
+        assert_eq!(log(&["-vvv"]).level, Level::TRACE);
+        assert_eq!(log(&["-q"]).level, Level::WARN);
+        assert_eq!(log(&["--quiet", "--quiet", "--quiet"]).level, Level::ERROR);
+        assert_eq!(log(&["--log-format", "json"]).format, LogFormat::Json);
+
+        for args in [&["-qq"][..], &["-v", "--log-format", "json"], &[]] {
+            assert_eq!(log(args).to_args(), args);
+        }
+    }
+}
@@ -0,0 +1,319 @@
+use std::collections::HashMap;
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::sync::{Mutex, OnceLock};
+use std::thread;
+use std::time::Duration;
+
+use prometheus::{
+    Encoder, HistogramOpts, HistogramVec, IntCounter, IntCounterVec, IntGaugeVec, Opts, Registry, TextEncoder,
+};
+use redis::{ConnectionLike, RedisResult, Value};
+
+use crate::{connect, AnyConnection, Config};
+
+/// The processing time buckets, from 1 ms to 10 s
+const PROCESSING_BUCKETS: [f64; 12] = [0.001, 0.0025, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 10.0];
+
+/// The Prometheus metrics of the producers and consumers.
+/// The counters are updated by the examples as they go; the stream length, pending count and
+/// lag of the watched streams are read from the server whenever the endpoint is scraped.
+pub struct Metrics {
+    registry: Registry,
+    produced: IntCounterVec,
+    consumed: IntCounterVec,
+    acked: IntCounterVec,
+    claimed: IntCounterVec,
+    dead_lettered: IntCounterVec,
+    deleted_pending: IntCounterVec,
+    processing: HistogramVec,
+    reconnects: IntCounter,
+    stream_length: IntGaugeVec,
+    pending: IntGaugeVec,
+    lag: IntGaugeVec,
+    watched: Mutex<Vec<String>>,
+}
+
+impl Metrics {
+    pub fn new() -> Metrics {
+        let registry = Registry::new_custom(Some("rrbe".to_string()), None).unwrap();
+        let metrics = Metrics {
+            produced: IntCounterVec::new(
+                Opts::new("entries_produced_total", "Entries added to a stream"),
+                &["stream"],
+            )
+            .unwrap(),
+            consumed: IntCounterVec::new(
+                Opts::new("entries_consumed_total", "Entries read from a stream by a consumer group"),
+                &["stream", "group"],
+            )
+            .unwrap(),
+            acked: IntCounterVec::new(Opts::new("acks_total", "Entries acknowledged with XACK"), &["stream", "group"])
+                .unwrap(),
+            claimed: IntCounterVec::new(
+                Opts::new("entries_claimed_total", "Idle entries taken over from other consumers"),
+                &["stream", "group"],
+            )
+            .unwrap(),
+            dead_lettered: IntCounterVec::new(
+                Opts::new("entries_dead_lettered_total", "Entries moved to the dead-letter stream"),
+                &["stream", "group"],
+            )
+            .unwrap(),
+            deleted_pending: IntCounterVec::new(
+                Opts::new("deleted_pending_total", "Pending entries deleted from the stream before they were handled"),
+                &["stream", "group"],
+            )
+            .unwrap(),
+            processing: HistogramVec::new(
+                HistogramOpts::new("processing_seconds", "Time from reading an entry to acknowledging it")
+                    .buckets(PROCESSING_BUCKETS.to_vec()),
+                &["stream", "group"],
+            )
+            .unwrap(),
+            reconnects: IntCounter::new("reconnects_total", "Connections re-established after they were lost").unwrap(),
+            stream_length: IntGaugeVec::new(Opts::new("stream_length", "XLEN of the stream"), &["stream"]).unwrap(),
+            pending: IntGaugeVec::new(
+                Opts::new("pending_entries", "Entries delivered to a group but not acknowledged yet"),
+                &["stream", "group"],
+            )
+            .unwrap(),
+            lag: IntGaugeVec::new(
+                Opts::new("group_lag", "Entries not delivered to the group yet (Redis 7.0+)"),
+                &["stream", "group"],
+            )
+            .unwrap(),
+            watched: Mutex::new(vec![]),
+            registry,
+        };
+        // The names are unique, so registering can't fail
+        metrics.registry.register(Box::new(metrics.produced.clone())).unwrap();
+        metrics.registry.register(Box::new(metrics.consumed.clone())).unwrap();
+        metrics.registry.register(Box::new(metrics.acked.clone())).unwrap();
+        metrics.registry.register(Box::new(metrics.claimed.clone())).unwrap();
+        metrics.registry.register(Box::new(metrics.dead_lettered.clone())).unwrap();
+        metrics.registry.register(Box::new(metrics.deleted_pending.clone())).unwrap();
+        metrics.registry.register(Box::new(metrics.processing.clone())).unwrap();
+        metrics.registry.register(Box::new(metrics.reconnects.clone())).unwrap();
+        metrics.registry.register(Box::new(metrics.stream_length.clone())).unwrap();
+        metrics.registry.register(Box::new(metrics.pending.clone())).unwrap();
+        metrics.registry.register(Box::new(metrics.lag.clone())).unwrap();
+        metrics
+    }
+
+    pub fn produced(&self, stream: &str) {
+        self.produced.with_label_values(&[stream]).inc();
+    }
+
+    pub fn consumed(&self, stream: &str, group: &str, count: usize) {
+        self.consumed.with_label_values(&[stream, group]).inc_by(count as u64);
+    }
+
+    pub fn acked(&self, stream: &str, group: &str, count: usize) {
+        self.acked.with_label_values(&[stream, group]).inc_by(count as u64);
+    }
+
+    pub fn claimed(&self, stream: &str, group: &str, count: usize) {
+        self.claimed.with_label_values(&[stream, group]).inc_by(count as u64);
+    }
+
+    pub fn dead_lettered(&self, stream: &str, group: &str) {
+        self.dead_lettered.with_label_values(&[stream, group]).inc();
+    }
+
+    pub fn deleted_pending(&self, stream: &str, group: &str, count: usize) {
+        self.deleted_pending.with_label_values(&[stream, group]).inc_by(count as u64);
+    }
+
+    pub fn observe_processing(&self, stream: &str, group: &str, elapsed: Duration) {
+        self.processing.with_label_values(&[stream, group]).observe(elapsed.as_secs_f64());
+    }
+
+    pub fn reconnected(&self) {
+        self.reconnects.inc();
+    }
+
+    /// Report the length of the stream, and the pending count and lag of each of its groups
+    pub fn watch(&self, stream: &str) {
+        let mut watched = self.watched.lock().unwrap();
+        if !watched.iter().any(|s| s == stream) {
+            watched.push(stream.to_string());
+        }
+    }
+
+    /// Read the gauges of the watched streams from the server
+    fn refresh<C: ConnectionLike>(&self, con: &mut C) -> RedisResult<()> {
+        let watched = self.watched.lock().unwrap().clone();
+        for stream in &watched {
+            let length: i64 = redis::cmd("XLEN").arg(stream).query(con)?;
+            self.stream_length.with_label_values(&[stream]).set(length);
+            // A stream that doesn't exist yet has no groups
+            let groups: Vec<HashMap<String, Value>> = match length {
+                0 => vec![],
+                _ => redis::cmd("XINFO").arg("GROUPS").arg(stream).query(con)?,
+            };
+            for group in &groups {
+                self.set_group_gauges(stream, group);
+            }
+        }
+        Ok(())
+    }
+
+    /// The `pending` and `lag` fields of an XINFO GROUPS entry.  There is no lag before
+    /// Redis 7.0, and it is nil while the server can't tell, e.g. after entries were deleted.
+    fn set_group_gauges(&self, stream: &str, group: &HashMap<String, Value>) {
+        let int = |field: &str| match group.get(field) {
+            Some(Value::Int(n)) => Some(*n),
+            _ => None,
+        };
+        let name = match group.get("name") {
+            Some(Value::Data(name)) => String::from_utf8_lossy(name).into_owned(),
+            _ => return,
+        };
+        if let Some(pending) = int("pending") {
+            self.pending.with_label_values(&[stream, &name]).set(pending);
+        }
+        match int("lag") {
+            Some(lag) => self.lag.with_label_values(&[stream, &name]).set(lag),
+            None => {
+                let _ = self.lag.remove_label_values(&[stream, &name]);
+            }
+        }
+    }
+
+    /// The metrics in the Prometheus text format
+    pub fn render(&self) -> String {
+        let mut buffer = vec![];
+        // Encoding into a Vec can't fail
+        TextEncoder::new().encode(&self.registry.gather(), &mut buffer).unwrap();
+        String::from_utf8(buffer).unwrap()
+    }
+}
+
+impl Default for Metrics {
+    fn default() -> Metrics {
+        Metrics::new()
+    }
+}
+
+/// The metrics of this process.  They are always counted, but only served with `serve_metrics`.
+pub fn metrics() -> &'static Metrics {
+    static METRICS: OnceLock<Metrics> = OnceLock::new();
+    METRICS.get_or_init(Metrics::new)
+}
+
+/// Serve `metrics()` at `http://<addr>/metrics` from a background thread, e.g. for
+/// `curl localhost:9100/metrics`.  Returns the address that was bound, which tells the port
+/// when `addr` asks for any free one with port 0.
+pub fn serve_metrics(addr: SocketAddr, config: &Config) -> io::Result<SocketAddr> {
+    serve(addr, config.clone(), metrics())
+}
+
+fn serve(addr: SocketAddr, config: Config, metrics: &'static Metrics) -> io::Result<SocketAddr> {
+    let listener = TcpListener::bind(addr)?;
+    let local_addr = listener.local_addr()?;
+    thread::Builder::new().name("metrics".to_string()).spawn(move || {
+        // The connection for the gauges is opened on the first scrape, and again after it broke
+        let mut con: Option<AnyConnection> = None;
+        for stream in listener.incoming() {
+            let result = stream.and_then(|stream| respond(stream, &config, &mut con, metrics));
+            if let Err(e) = result {
+                tracing::debug!(error = %e, "Metrics request failed");
+            }
+        }
+    })?;
+    tracing::info!(addr = %local_addr, "Serving metrics");
+    Ok(local_addr)
+}
+
+fn respond(mut stream: TcpStream, config: &Config, con: &mut Option<AnyConnection>, metrics: &Metrics) -> io::Result<()> {
+    stream.set_read_timeout(Some(Duration::from_secs(5)))?;
+    let mut request_line = String::new();
+    BufReader::new(&stream).read_line(&mut request_line)?;
+    let path = request_line.split_whitespace().nth(1).unwrap_or("");
+
+    if path != "/metrics" {
+        return write!(stream, "HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n");
+    }
+
+    if con.is_none() && !metrics.watched.lock().unwrap().is_empty() {
+        *con = connect(config).map_err(|e| tracing::warn!(error = %e, "Metrics could not connect")).ok();
+    }
+    if let Some(c) = con.as_mut() {
+        if let Err(e) = metrics.refresh(c) {
+            tracing::warn!(error = %e, "Metrics could not read the streams");
+            *con = None;
+        }
+    }
+
+    let body = metrics.render();
+    write!(
+        stream,
+        "HTTP/1.1 200 OK\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        prometheus::TEXT_FORMAT,
+        body.len(),
+        body
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Read;
+
+    use super::*;
+
+    #[test]
+    fn test_render() {
+        let metrics = Metrics::new();
+        metrics.produced("stream:{numbers}");
+        metrics.consumed("stream:{numbers}", "primes", 3);
+        metrics.acked("stream:{numbers}", "primes", 2);
+        metrics.observe_processing("stream:{numbers}", "primes", Duration::from_millis(3));
+
+        let text = metrics.render();
+        assert!(text.contains("rrbe_entries_produced_total{stream=\"stream:{numbers}\"} 1"), "{}", text);
+        assert!(text.contains("rrbe_entries_consumed_total{group=\"primes\",stream=\"stream:{numbers}\"} 3"), "{}", text);
+        assert!(text.contains("rrbe_acks_total{group=\"primes\",stream=\"stream:{numbers}\"} 2"), "{}", text);
+        assert!(text.contains("rrbe_processing_seconds_bucket{group=\"primes\",stream=\"stream:{numbers}\",le=\"0.005\"} 1"));
+    }
+
+    #[test]
+    fn test_group_gauges() {
+        let metrics = Metrics::new();
+        let group = |lag: Value| {
+            HashMap::from([
+                ("name".to_string(), Value::Data(b"primes".to_vec())),
+                ("pending".to_string(), Value::Int(4)),
+                ("lag".to_string(), lag),
+            ])
+        };
+        metrics.set_group_gauges("s", &group(Value::Int(7)));
+        assert!(metrics.render().contains("rrbe_group_lag{group=\"primes\",stream=\"s\"} 7"));
+        // The lag is unknown: leave it out rather than report a stale value
+        metrics.set_group_gauges("s", &group(Value::Nil));
+        let text = metrics.render();
+        assert!(!text.contains("rrbe_group_lag{"), "{}", text);
+        assert!(text.contains("rrbe_pending_entries{group=\"primes\",stream=\"s\"} 4"));
+    }
+
+    #[test]
+    fn test_scrape() {
+        let metrics: &'static Metrics = Box::leak(Box::new(Metrics::new()));
+        metrics.reconnected();
+        // Nothing is watched, so no server is needed
+        let config = Config { port: 1, ..Config::default() };
+        let addr = serve("127.0.0.1:0".parse().unwrap(), config, metrics).unwrap();
+
+        let get = |path: &str| {
+            let mut stream = TcpStream::connect(addr).unwrap();
+            write!(stream, "GET {} HTTP/1.1\r\nHost: localhost\r\n\r\n", path).unwrap();
+            let mut response = String::new();
+            stream.read_to_string(&mut response).unwrap();
+            response
+        };
+        let response = get("/metrics");
+        assert!(response.starts_with("HTTP/1.1 200 OK"), "{}", response);
+        assert!(response.contains("rrbe_reconnects_total 1"), "{}", response);
+        assert!(get("/").starts_with("HTTP/1.1 404"));
+    }
+}
@@ -0,0 +1,130 @@
+//! Replay entries at the pace they were added: the milliseconds of their IDs tell how far
+//! apart they came, and a speed factor shrinks or stretches the gaps
+
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use crate::StreamId;
+
+/// Timestamps below this are in seconds, above it in milliseconds: 10^11 seconds is
+/// more than 3000 years from the epoch, 10^11 milliseconds a little over 3 years
+const SECONDS_BELOW: u64 = 100_000_000_000;
+
+/// The slowest and the fastest speeds `parse_speed` accepts
+const MIN_SPEED: f64 = 0.001;
+const MAX_SPEED: f64 = 1000.0;
+
+/// When to replay each entry, relative to when the first one was replayed
+#[derive(Clone, Debug)]
+pub struct Pacing {
+    speed: f64,
+    origin: Option<Origin>,
+}
+
+/// The first entry of a run, and when it was replayed
+#[derive(Clone, Copy, Debug)]
+struct Origin {
+    id_ms: u64,
+    at: Instant,
+    wall_ms: u64,
+}
+
+impl Pacing {
+    /// `speed` 1.0 replays in real time, 10.0 ten times faster, 0.5 at half the speed
+    pub fn new(speed: f64) -> Pacing {
+        Pacing { speed, origin: None }
+    }
+
+    /// When to replay the entry `id`.  The first entry after `new` or `restart` is due now.
+    /// `None` if it is too far in the future to tell, i.e. never.
+    pub fn due(&mut self, id: StreamId) -> Option<Instant> {
+        let origin = *self.origin.get_or_insert_with(|| Origin { id_ms: id.ms, at: Instant::now(), wall_ms: now_ms() });
+        origin.at.checked_add(self.scale(id.ms.saturating_sub(origin.id_ms)))
+    }
+
+    /// Start over, e.g. to loop: the next entry is due right away, whatever its ID
+    pub fn restart(&mut self) {
+        self.origin = None;
+    }
+
+    /// A timestamp of an entry moved to the replay's time, like its ID: in seconds or
+    /// milliseconds since the epoch, whichever it was.  `None` before the first `due`.
+    pub fn rewrite(&self, timestamp: u64) -> Option<u64> {
+        let origin = self.origin?;
+        let (ms, unit) = if timestamp < SECONDS_BELOW { (timestamp.saturating_mul(1000), 1000) } else { (timestamp, 1) };
+        let millis = |gap| u64::try_from(self.scale(gap).as_millis()).unwrap_or(u64::MAX);
+        let rewritten = if ms >= origin.id_ms {
+            origin.wall_ms.saturating_add(millis(ms - origin.id_ms))
+        } else {
+            origin.wall_ms.saturating_sub(millis(origin.id_ms - ms))
+        };
+        Some(rewritten / unit)
+    }
+
+    /// A gap between two entries, at the speed of the replay, or `Duration::MAX` if it is longer
+    fn scale(&self, ms: u64) -> Duration {
+        Duration::try_from_secs_f64(ms as f64 / 1000.0 / self.speed).unwrap_or(Duration::MAX)
+    }
+}
+
+fn now_ms() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_millis() as u64)
+}
+
+/// A speed factor like `10x`, `0.5x` or `2`, from `MIN_SPEED` to `MAX_SPEED`
+pub fn parse_speed(s: &str) -> Option<f64> {
+    let speed: f64 = s.trim().strip_suffix('x').unwrap_or(s.trim()).parse().ok()?;
+    (MIN_SPEED..=MAX_SPEED).contains(&speed).then_some(speed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_speed() {
+        assert_eq!(parse_speed("10x"), Some(10.0));
+        assert_eq!(parse_speed("0.5x"), Some(0.5));
+        assert_eq!(parse_speed("2"), Some(2.0));
+        assert_eq!(parse_speed("0x"), None);
+        assert_eq!(parse_speed("-1"), None);
+        assert_eq!(parse_speed("fast"), None);
+        assert_eq!(parse_speed("0.001x"), Some(MIN_SPEED));
+        assert_eq!(parse_speed("1000x"), Some(MAX_SPEED));
+        assert_eq!(parse_speed("0.0001x"), None);
+        assert_eq!(parse_speed("1e300"), None);
+        assert_eq!(parse_speed("NaN"), None);
+        assert_eq!(parse_speed("inf"), None);
+    }
+
+    #[test]
+    fn test_pacing() {
+        let mut pacing = Pacing::new(10.0);
+        assert_eq!(pacing.rewrite(1_000_000_000_000), None);
+
+        let first = pacing.due(StreamId::new(1_000_000_000_000, 0)).unwrap();
+        // 5 seconds later in the recording, half a second later in the replay
+        assert_eq!(pacing.due(StreamId::new(1_000_000_005_000, 3)).unwrap() - first, Duration::from_millis(500));
+        // Out of order timestamps don't go back in time
+        assert_eq!(pacing.due(StreamId::new(999_999_999_000, 0)), Some(first));
+
+        let origin = pacing.origin.unwrap().wall_ms;
+        assert_eq!(pacing.rewrite(1_000_000_005_000), Some(origin + 500));
+        assert_eq!(pacing.rewrite(999_999_990_000), Some(origin - 1000));
+        // In seconds
+        assert_eq!(pacing.rewrite(1_000_000_010), Some((origin + 1000) / 1000));
+
+        pacing.restart();
+        let again = pacing.due(StreamId::new(1_000_000_000_000, 0)).unwrap();
+        assert!(again >= first && again - first < Duration::from_secs(1));
+    }
+
+    #[test]
+    fn test_pacing_far_apart() {
+        // The last entry of a stream that began at the epoch, at the slowest speed
+        let mut pacing = Pacing::new(1e-300);
+        assert_eq!(pacing.scale(u64::MAX), Duration::MAX);
+        assert!(pacing.due(StreamId::new(0, 0)).is_some());
+        assert_eq!(pacing.due(StreamId::MAX), None);
+        assert_eq!(pacing.rewrite(u64::MAX), Some(u64::MAX));
+    }
+}
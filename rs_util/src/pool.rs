@@ -0,0 +1,140 @@
+use std::error;
+use std::fmt;
+use std::time::Duration;
+
+use redis::{ConnectionLike, RedisError};
+
+use crate::{connect, AnyConnection, Config};
+
+/// A pool of connections to the configured server, which can be cloned and shared between threads
+pub type Pool = r2d2::Pool<ConnectionManager>;
+
+/// A connection checked out of a `Pool`.  It goes back into the pool when dropped.
+pub type PooledConnection = r2d2::PooledConnection<ConnectionManager>;
+
+/// Opens connections for a `Pool` exactly like `connect` does
+#[derive(Clone, Debug)]
+pub struct ConnectionManager {
+    config: Config,
+}
+
+impl ConnectionManager {
+    pub fn new(config: Config) -> ConnectionManager {
+        ConnectionManager { config }
+    }
+}
+
+impl r2d2::ManageConnection for ConnectionManager {
+    type Connection = AnyConnection;
+    type Error = RedisError;
+
+    fn connect(&self) -> Result<AnyConnection, RedisError> {
+        connect(&self.config)
+    }
+
+    /// A connection that sat idle in the pool is checked with a PING before it is handed out.
+    /// Behind Sentinel, it must also still be connected to the master.
+    fn is_valid(&self, con: &mut AnyConnection) -> Result<(), RedisError> {
+        match (con, &self.config.sentinel) {
+            (AnyConnection::Single(con), Some(_)) => crate::sentinel::check_master(con),
+            (con, _) => redis::cmd("PING").query(con),
+        }
+    }
+
+    fn has_broken(&self, con: &mut AnyConnection) -> bool {
+        !con.is_open()
+    }
+}
+
+/// Settings for `pool_with_options`
+#[derive(Clone, Debug)]
+pub struct PoolOptions {
+    /// The maximum number of connections
+    pub size: u32,
+    /// How long to wait for a free connection before `Pool::get` fails
+    pub checkout_timeout: Duration,
+    /// PING every connection when it is checked out, and replace it if there is no answer
+    pub health_check: bool,
+    /// Close connections that have been idle for longer than this
+    pub idle_timeout: Option<Duration>,
+    /// Close connections that are older than this, even if they are still healthy
+    pub max_lifetime: Option<Duration>,
+}
+
+impl Default for PoolOptions {
+    fn default() -> PoolOptions {
+        PoolOptions {
+            size: 10,
+            checkout_timeout: Duration::from_secs(5),
+            health_check: true,
+            idle_timeout: Some(Duration::from_secs(5 * 60)),
+            max_lifetime: Some(Duration::from_secs(30 * 60)),
+        }
+    }
+}
+
+/// The error returned when a pool cannot be created
+#[derive(Debug)]
+pub enum PoolError {
+    /// The connection settings are not usable
+    Redis(RedisError),
+    /// The pool could not open its first connection before the checkout timeout
+    Pool(r2d2::Error),
+}
+
+impl fmt::Display for PoolError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PoolError::Redis(e) => write!(f, "invalid connection settings: {}", e),
+            PoolError::Pool(e) => write!(f, "could not fill the connection pool: {}", e),
+        }
+    }
+}
+
+impl error::Error for PoolError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            PoolError::Redis(e) => Some(e),
+            PoolError::Pool(e) => Some(e),
+        }
+    }
+}
+
+/// Create a pool of up to `size` connections with the default `PoolOptions`
+pub fn pool(config: &Config, size: u32) -> Result<Pool, PoolError> {
+    pool_with_options(config, &PoolOptions { size, ..PoolOptions::default() })
+}
+
+/// Create a pool of connections.
+/// At least one connection is opened right away, so a bad address or password fails here
+/// instead of on the first checkout.
+pub fn pool_with_options(config: &Config, options: &PoolOptions) -> Result<Pool, PoolError> {
+    // Fail early on settings that can never work, instead of timing out on them
+    config.connection_info().map_err(PoolError::Redis)?;
+
+    r2d2::Pool::builder()
+        .max_size(options.size)
+        .min_idle(Some(1))
+        .connection_timeout(options.checkout_timeout)
+        .test_on_check_out(options.health_check)
+        .idle_timeout(options.idle_timeout)
+        .max_lifetime(options.max_lifetime)
+        .build(ConnectionManager::new(config.clone()))
+        .map_err(PoolError::Pool)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::TlsConfig;
+
+    #[test]
+    fn test_unusable_settings_fail_fast() {
+        let config = Config {
+            socket: Some("/tmp/redis.sock".into()),
+            tls: Some(TlsConfig::default()),
+            ..Config::default()
+        };
+        assert!(matches!(pool(&config, 2), Err(PoolError::Redis(_))));
+    }
+}
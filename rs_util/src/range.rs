@@ -0,0 +1,62 @@
+//! Read a whole stream, or a part of it, with XRANGE, a page of entries at a time
+
+use redis::streams::{StreamId as Entry, StreamRangeReply};
+use redis::{Commands, ConnectionLike};
+
+use crate::{Context, EntryId, Result, StreamId};
+
+/// The entries of a stream from `start` to `end`, `count` at a time, e.g.
+/// `for page in RangePages::new(&mut con, "numbers", StreamId::MIN, EntryId::Max, 100)`.
+/// It ends at the first empty page, or after the first error.
+pub struct RangePages<'a, C> {
+    con: &'a mut C,
+    key: String,
+    next: Option<StreamId>,
+    end: EntryId,
+    count: usize,
+    done: bool,
+}
+
+impl<'a, C: ConnectionLike> RangePages<'a, C> {
+    pub fn new(con: &'a mut C, key: &str, start: StreamId, end: EntryId, count: usize) -> RangePages<'a, C> {
+        RangePages { con, key: key.to_string(), next: Some(start), end, count, done: false }
+    }
+
+    /// Where the next page starts, which is where to resume a read that was stopped or failed.
+    /// `None` once the highest possible ID was read.
+    pub fn next_id(&self) -> Option<StreamId> {
+        self.next
+    }
+
+    fn page(&mut self, start: StreamId) -> Result<Vec<Entry>> {
+        let reply: StreamRangeReply = self
+            .con
+            .xrange_count(&self.key, start, self.end, self.count)
+            .with_context(|| format!("reading a range of entries from {}", self.key))?;
+        // There can be no entries after the highest possible ID
+        if let Some(last) = reply.ids.last() {
+            self.next = last.id.parse::<StreamId>()?.next();
+        }
+        Ok(reply.ids)
+    }
+}
+
+impl<'a, C: ConnectionLike> Iterator for RangePages<'a, C> {
+    type Item = Result<Vec<Entry>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let start = self.next.filter(|_| !self.done)?;
+        match self.page(start) {
+            // An empty page means the range is exhausted
+            Ok(entries) if entries.is_empty() => {
+                self.done = true;
+                None
+            }
+            Ok(entries) => Some(Ok(entries)),
+            Err(e) => {
+                self.done = true;
+                Some(Err(e))
+            }
+        }
+    }
+}
@@ -0,0 +1,344 @@
+use std::fmt;
+use std::thread;
+use std::time::Duration;
+
+use rand::prelude::*;
+use redis::{ConnectionLike, ErrorKind, RedisError, RedisResult, Value};
+
+use crate::{connect, AnyConnection, Config, Shutdown};
+
+/// How long to wait between attempts to reconnect.
+/// The delay doubles with every attempt up to `max`, and is randomized ("jittered") so that many
+/// clients don't all come back at the same moment after a server restart.
+#[derive(Clone, Debug)]
+pub struct Backoff {
+    /// The delay before the first attempt
+    pub initial: Duration,
+    /// The longest delay between two attempts
+    pub max: Duration,
+    /// Give up after this many failed attempts.  `None` keeps trying forever.
+    pub max_attempts: Option<u32>,
+}
+
+impl Default for Backoff {
+    fn default() -> Backoff {
+        Backoff {
+            initial: Duration::from_millis(100),
+            max: Duration::from_secs(10),
+            max_attempts: None,
+        }
+    }
+}
+
+impl Backoff {
+    /// The delay before the given attempt (starting at 0): half of it fixed and half of it random
+    pub fn delay(&self, attempt: u32) -> Duration {
+        let ceiling = self.initial.saturating_mul(1 << attempt.min(20)).min(self.max);
+        let half = ceiling / 2;
+        half + half.mul_f64(thread_rng().gen::<f64>())
+    }
+}
+
+/// Whether a command is sent again after the connection has been re-established
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Retry {
+    /// Reconnect and send the command again.  Fine for reads and other idempotent commands.
+    Always,
+    /// Return the error.  The connection is re-established for the next command.
+    /// Use this for commands that must not run twice, e.g. `XADD *`, because the server may
+    /// have executed the command before the connection broke.
+    Never,
+}
+
+/// What happened to the connection, passed to the `on_event` callback
+#[derive(Clone, Debug)]
+pub enum ReconnectEvent {
+    /// The connection broke
+    Disconnected { error: String },
+    /// Connecting failed.  The next attempt is made after `delay`.
+    Retrying { attempt: u32, delay: Duration, error: String },
+    /// The connection is back after the given number of attempts
+    Reconnected { attempts: u32 },
+    /// `Backoff::max_attempts` was reached
+    GaveUp { attempts: u32, error: String },
+}
+
+impl fmt::Display for ReconnectEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReconnectEvent::Disconnected { error } => write!(f, "Connection lost: {}", error),
+            ReconnectEvent::Retrying { attempt, delay, error } => write!(
+                f,
+                "Reconnect attempt {} failed ({}); trying again in {} ms",
+                attempt,
+                error,
+                delay.as_millis()
+            ),
+            ReconnectEvent::Reconnected { attempts } => write!(f, "Reconnected after {} attempt(s)", attempts),
+            ReconnectEvent::GaveUp { attempts, error } => {
+                write!(f, "Giving up after {} reconnect attempts: {}", attempts, error)
+            }
+        }
+    }
+}
+
+type EventCallback = Box<dyn FnMut(&ReconnectEvent) + Send>;
+
+/// True for errors that mean the connection itself is gone, as opposed to an error reply.
+/// With Sentinels, READONLY counts too: after a failover the old master is a replica, and
+/// reconnecting finds the new master.  Without them, reconnecting finds the same replica again.
+fn is_connection_error(e: &RedisError, sentinel: bool) -> bool {
+    e.is_connection_dropped()
+        || e.is_connection_refusal()
+        || e.is_timeout()
+        || e.is_io_error()
+        || (sentinel && e.kind() == ErrorKind::ReadOnly)
+}
+
+/// A connection that re-establishes itself when the server goes away, e.g. during a restart.
+///
+/// Reconnecting goes through `connect`, which authenticates, selects the configured DB and names
+/// the connection again (and asks the Sentinels for the current master, if configured).
+/// It implements `ConnectionLike`, so all of redis-rs' `Commands` can be used with it.
+pub struct ResilientConnection {
+    config: Config,
+    con: Option<AnyConnection>,
+    backoff: Backoff,
+    retry: Retry,
+    on_event: Option<EventCallback>,
+    shutdown: Option<Shutdown>,
+}
+
+impl ResilientConnection {
+    /// Create the connection.  It is opened with the first command.
+    pub fn new(config: Config) -> ResilientConnection {
+        ResilientConnection {
+            config,
+            con: None,
+            backoff: Backoff::default(),
+            retry: Retry::Always,
+            on_event: None,
+            shutdown: None,
+        }
+    }
+
+    pub fn backoff(mut self, backoff: Backoff) -> Self {
+        self.backoff = backoff;
+        self
+    }
+
+    /// The retry policy for commands sent directly on this connection
+    pub fn retry(mut self, retry: Retry) -> Self {
+        self.retry = retry;
+        self
+    }
+
+    /// Name the connection with CLIENT SETNAME every time it is opened, instead of `Config::client_name`
+    pub fn client_name(mut self, name: &str) -> Self {
+        self.config.client_name = name.to_string();
+        self
+    }
+
+    /// Call `callback` whenever the connection is lost or re-established.  The events are
+    /// logged either way.
+    pub fn on_event<F>(mut self, callback: F) -> Self
+    where
+        F: FnMut(&ReconnectEvent) + Send + 'static,
+    {
+        self.on_event = Some(Box::new(callback));
+        self
+    }
+
+    /// Stop reconnecting once `shutdown` is triggered, instead of backing off forever.
+    /// The command that was waiting for the connection then fails.
+    pub fn shutdown(mut self, shutdown: Shutdown) -> Self {
+        self.shutdown = Some(shutdown);
+        self
+    }
+
+    /// Send the next command(s) with the given retry policy instead of the connection's, e.g.
+    /// `con.with_retry(Retry::Never).xadd(...)`
+    pub fn with_retry(&mut self, retry: Retry) -> WithRetry<'_> {
+        WithRetry { con: self, retry }
+    }
+
+    /// Log the event, then pass it on to the callback
+    fn emit(&mut self, event: ReconnectEvent) {
+        match event {
+            ReconnectEvent::Reconnected { .. } => {
+                tracing::info!("{}", event);
+                crate::metrics().reconnected();
+            }
+            ReconnectEvent::GaveUp { .. } => tracing::error!("{}", event),
+            _ => tracing::warn!("{}", event),
+        }
+        if let Some(callback) = self.on_event.as_mut() {
+            callback(&event);
+        }
+    }
+
+    /// Open the connection if it isn't open, backing off between failed attempts
+    fn connection(&mut self) -> RedisResult<&mut AnyConnection> {
+        if self.con.is_none() {
+            let con = self.open()?;
+            self.con = Some(con);
+        }
+        Ok(self.con.as_mut().unwrap())
+    }
+
+    fn open(&mut self) -> RedisResult<AnyConnection> {
+        let mut attempt = 0;
+        loop {
+            match connect(&self.config) {
+                Ok(con) => {
+                    if attempt > 0 {
+                        self.emit(ReconnectEvent::Reconnected { attempts: attempt + 1 });
+                    }
+                    return Ok(con);
+                }
+                // Bad credentials and the like won't get better by trying again
+                Err(e) if !is_connection_error(&e, self.config.sentinel.is_some()) => return Err(e),
+                Err(e) => {
+                    attempt += 1;
+                    if self.backoff.max_attempts.is_some_and(|max| attempt >= max) {
+                        self.emit(ReconnectEvent::GaveUp { attempts: attempt, error: e.to_string() });
+                        return Err(e);
+                    }
+                    let delay = self.backoff.delay(attempt - 1);
+                    self.emit(ReconnectEvent::Retrying { attempt, delay, error: e.to_string() });
+                    let shut_down = match &self.shutdown {
+                        Some(shutdown) => shutdown.sleep(delay),
+                        None => {
+                            thread::sleep(delay);
+                            false
+                        }
+                    };
+                    if shut_down {
+                        return Err((ErrorKind::ClientError, "Shutting down", e.to_string()).into());
+                    }
+                }
+            }
+        }
+    }
+
+    /// Run a request, reconnecting when the connection is broken and retrying if allowed
+    fn request<T, F>(&mut self, retry: Retry, mut f: F) -> RedisResult<T>
+    where
+        F: FnMut(&mut AnyConnection) -> RedisResult<T>,
+    {
+        loop {
+            match f(self.connection()?) {
+                Err(e) if is_connection_error(&e, self.config.sentinel.is_some()) => {
+                    self.con = None;
+                    self.emit(ReconnectEvent::Disconnected { error: e.to_string() });
+                    if retry == Retry::Never {
+                        return Err(e);
+                    }
+                }
+                result => return result,
+            }
+        }
+    }
+}
+
+impl ConnectionLike for ResilientConnection {
+    fn req_packed_command(&mut self, cmd: &[u8]) -> RedisResult<Value> {
+        let retry = self.retry;
+        self.request(retry, |con| con.req_packed_command(cmd))
+    }
+
+    fn req_packed_commands(&mut self, cmd: &[u8], offset: usize, count: usize) -> RedisResult<Vec<Value>> {
+        let retry = self.retry;
+        self.request(retry, |con| con.req_packed_commands(cmd, offset, count))
+    }
+
+    fn get_db(&self) -> i64 {
+        self.con.as_ref().map_or(self.config.db, |con| con.get_db())
+    }
+
+    fn check_connection(&mut self) -> bool {
+        self.request(Retry::Always, |con| redis::cmd("PING").query::<()>(con)).is_ok()
+    }
+
+    fn is_open(&self) -> bool {
+        self.con.as_ref().is_some_and(|con| con.is_open())
+    }
+}
+
+/// A `ResilientConnection` borrowed with a different retry policy, see `ResilientConnection::with_retry`
+pub struct WithRetry<'a> {
+    con: &'a mut ResilientConnection,
+    retry: Retry,
+}
+
+impl ConnectionLike for WithRetry<'_> {
+    fn req_packed_command(&mut self, cmd: &[u8]) -> RedisResult<Value> {
+        self.con.request(self.retry, |con| con.req_packed_command(cmd))
+    }
+
+    fn req_packed_commands(&mut self, cmd: &[u8], offset: usize, count: usize) -> RedisResult<Vec<Value>> {
+        self.con.request(self.retry, |con| con.req_packed_commands(cmd, offset, count))
+    }
+
+    fn get_db(&self) -> i64 {
+        self.con.get_db()
+    }
+
+    fn check_connection(&mut self) -> bool {
+        self.con.check_connection()
+    }
+
+    fn is_open(&self) -> bool {
+        self.con.is_open()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use super::*;
+
+    #[test]
+    fn test_backoff_delay() {
+        let backoff = Backoff {
+            initial: Duration::from_millis(100),
+            max: Duration::from_secs(1),
+            max_attempts: None,
+        };
+        for (attempt, ceiling) in [(0, 100), (1, 200), (2, 400), (3, 800), (4, 1000), (40, 1000)] {
+            let delay = backoff.delay(attempt);
+            let ceiling = Duration::from_millis(ceiling);
+            assert!(delay >= ceiling / 2 && delay <= ceiling, "attempt {}: {:?}", attempt, delay);
+        }
+    }
+
+    #[test]
+    fn test_readonly_only_reconnects_with_sentinels() {
+        let readonly: RedisError = (ErrorKind::ReadOnly, "You can't write against a read only replica.").into();
+        assert!(is_connection_error(&readonly, true));
+        assert!(!is_connection_error(&readonly, false));
+    }
+
+    #[test]
+    fn test_gives_up_and_reports() {
+        // Nothing listens on port 1, so every attempt is refused
+        let config = Config { port: 1, ..Config::default() };
+        let events = Arc::new(Mutex::new(vec![]));
+        let seen = events.clone();
+        let mut con = ResilientConnection::new(config)
+            .backoff(Backoff {
+                initial: Duration::from_millis(1),
+                max: Duration::from_millis(2),
+                max_attempts: Some(3),
+            })
+            .on_event(move |event| seen.lock().unwrap().push(event.clone()));
+
+        assert!(redis::cmd("PING").query::<String>(&mut con).is_err());
+        assert!(!con.is_open());
+        let events = events.lock().unwrap();
+        assert_eq!(events.len(), 3);
+        assert!(matches!(events[0], ReconnectEvent::Retrying { attempt: 1, .. }));
+        assert!(matches!(events[2], ReconnectEvent::GaveUp { attempts: 3, .. }));
+    }
+}
@@ -0,0 +1,290 @@
+//! How much of a stream the producers keep.  Without a limit a stream grows for as long as
+//! entries are added.  The limit is applied on every XADD, or with XTRIM every so often.
+//!
+//! Trimming doesn't care about consumer groups: it can delete entries that were delivered but
+//! not acknowledged yet.  `GroupWorker` acknowledges such entries when it comes across them.
+
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use clap_v3::{App, Arg, ArgMatches};
+use redis::{Cmd, ConnectionLike, ToRedisArgs};
+
+use crate::{ConfigError, Feature, Result, StreamId};
+
+/// Which entries of a stream to keep
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Limit {
+    /// The newest entries, at most this many (MAXLEN)
+    MaxLen(usize),
+    /// The entries from this ID on (MINID)
+    MinId(StreamId),
+    /// The entries added during the last while, as a MINID computed at every trim
+    MaxAge(Duration),
+}
+
+/// The retention of a stream, see the module documentation.  The default keeps everything.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Retention {
+    limit: Option<Limit>,
+    approximate: bool,
+    every: Option<Duration>,
+    next_trim: Option<Instant>,
+}
+
+impl Retention {
+    /// Keep the entries within `limit`, trimming exactly on every XADD
+    pub fn new(limit: Limit) -> Retention {
+        Retention { limit: Some(limit), ..Retention::default() }
+    }
+
+    /// Let the server trim whole nodes only (`~`), which is much cheaper, keeping a few entries
+    /// more than the limit
+    pub fn approximate(mut self, approximate: bool) -> Self {
+        self.approximate = approximate;
+        self
+    }
+
+    /// Trim with XTRIM, at most once every `every`, instead of on every XADD
+    pub fn every(mut self, every: Duration) -> Self {
+        self.every = Some(every);
+        self
+    }
+
+    pub fn limit(&self) -> Option<Limit> {
+        self.limit
+    }
+
+    /// The feature the server needs for the limit, if it isn't there from the start
+    pub fn feature(&self) -> Option<Feature> {
+        match self.limit {
+            Some(Limit::MinId(_) | Limit::MaxAge(_)) => Some(Feature::XAddMinId),
+            _ => None,
+        }
+    }
+
+    /// Fail early if the server can't apply the limit, rather than on the first XADD
+    pub fn check<C: ConnectionLike>(&self, con: &mut C) -> Result<()> {
+        if let Some(feature) = self.feature() {
+            crate::capabilities(con)?.require(feature)?;
+        }
+        Ok(())
+    }
+
+    /// Append the limit to XADD or XTRIM
+    fn limit_args(&self, cmd: &mut Cmd) {
+        let exact = if self.approximate { "~" } else { "=" };
+        match self.limit {
+            None => {}
+            // Exact is the default, and `=` needs Redis 6.2 like MINID does
+            Some(Limit::MaxLen(len)) if !self.approximate => {
+                cmd.arg("MAXLEN").arg(len);
+            }
+            Some(Limit::MaxLen(len)) => {
+                cmd.arg("MAXLEN").arg("~").arg(len);
+            }
+            Some(Limit::MinId(id)) => {
+                cmd.arg("MINID").arg(exact).arg(id.to_string());
+            }
+            Some(Limit::MaxAge(age)) => {
+                let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default();
+                let min_id = StreamId::new(now.saturating_sub(age).as_millis() as u64, 0);
+                cmd.arg("MINID").arg(exact).arg(min_id.to_string());
+            }
+        }
+    }
+
+    /// XADD of a new entry with an automatic ID, which also trims unless trimming is periodic
+    pub fn xadd<F: ToRedisArgs, V: ToRedisArgs>(&self, key: &str, items: &[(F, V)]) -> Cmd {
+        let mut cmd = redis::cmd("XADD");
+        cmd.arg(key);
+        if self.every.is_none() {
+            self.limit_args(&mut cmd);
+        }
+        cmd.arg("*").arg(items);
+        cmd
+    }
+
+    /// XTRIM to the limit, if there is one.  The reply is the number of entries deleted.
+    pub fn xtrim(&self, key: &str) -> Option<Cmd> {
+        self.limit?;
+        let mut cmd = redis::cmd("XTRIM");
+        cmd.arg(key);
+        self.limit_args(&mut cmd);
+        Some(cmd)
+    }
+
+    /// The XTRIM to run now, when trimming is periodic and it is time for it
+    pub fn trim_due(&mut self, key: &str) -> Option<Cmd> {
+        let every = self.every?;
+        let now = Instant::now();
+        if self.next_trim.is_some_and(|next| now < next) {
+            return None;
+        }
+        self.next_trim = Some(now + every);
+        self.xtrim(key)
+    }
+
+    /// The retention set with the arguments of `retention_args`
+    pub fn from_matches(matches: &ArgMatches) -> std::result::Result<Retention, ConfigError> {
+        let (limit, approximate) = if let Some(value) = matches.value_of("MAXLEN") {
+            let (len, approximate) = approximate_value(value);
+            let len = len.parse().map_err(|_| ConfigError::from(format!("--maxlen: '{}' is not a number", value)))?;
+            (Limit::MaxLen(len), approximate)
+        } else if let Some(value) = matches.value_of("MIN_ID") {
+            let (id, approximate) = approximate_value(value);
+            let id = id.parse().map_err(|e| ConfigError::from(format!("--min-id: {}", e)))?;
+            (Limit::MinId(id), approximate)
+        } else if let Some(value) = matches.value_of("MAX_AGE") {
+            let (age, approximate) = approximate_value(value);
+            let age = parse_duration(age).ok_or_else(|| ConfigError::from(format!("--max-age: '{}' is not a duration", value)))?;
+            (Limit::MaxAge(age), approximate)
+        } else {
+            if matches.is_present("TRIM_EVERY") {
+                return Err(ConfigError::from("--trim-every needs --maxlen, --min-id or --max-age".to_string()));
+            }
+            return Ok(Retention::default());
+        };
+
+        let mut retention = Retention::new(limit).approximate(approximate);
+        if let Some(value) = matches.value_of("TRIM_EVERY") {
+            let every = parse_duration(value)
+                .ok_or_else(|| ConfigError::from(format!("--trim-every: '{}' is not a duration", value)))?;
+            retention = retention.every(every);
+        }
+        Ok(retention)
+    }
+}
+
+/// A limit, without its `~` (approximate) or `=` (exact) prefix, and whether it was approximate
+fn approximate_value(value: &str) -> (&str, bool) {
+    match value.strip_prefix('~') {
+        Some(rest) => (rest, true),
+        None => (value.strip_prefix('=').unwrap_or(value), false),
+    }
+}
+
+/// A duration like `500ms`, `90s`, `10m`, `2h` or `1d`.  A bare number is in seconds.
+pub fn parse_duration(s: &str) -> Option<Duration> {
+    let s = s.trim();
+    let split = s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len());
+    let (number, unit) = s.split_at(split);
+    let n: u64 = number.parse().ok()?;
+    let seconds = match unit {
+        "ms" => return Some(Duration::from_millis(n)),
+        "" | "s" => 1,
+        "m" => 60,
+        "h" => 60 * 60,
+        "d" => 24 * 60 * 60,
+        _ => return None,
+    };
+    Some(Duration::from_secs(n.checked_mul(seconds)?))
+}
+
+/// Add the retention arguments to the app of a producer, see `Retention::from_matches`
+pub fn retention_args(app: App<'_>) -> App<'_> {
+    app.arg(
+        Arg::with_name("MAXLEN")
+            .help("Keep the newest N entries of the stream; ~N trims approximately, which is cheaper")
+            .long("maxlen")
+            .value_name("N")
+            .takes_value(true)
+            .conflicts_with_all(&["MIN_ID", "MAX_AGE"]),
+    )
+    .arg(
+        Arg::with_name("MIN_ID")
+            .help("Delete the entries before ID, or ~ID approximately (Redis 6.2+)")
+            .long("min-id")
+            .value_name("ID")
+            .takes_value(true)
+            .conflicts_with("MAX_AGE"),
+    )
+    .arg(
+        Arg::with_name("MAX_AGE")
+            .help("Delete the entries older than AGE, e.g. 10m or ~2h (Redis 6.2+)")
+            .long("max-age")
+            .value_name("AGE")
+            .takes_value(true),
+    )
+    .arg(
+        Arg::with_name("TRIM_EVERY")
+            .help("Trim with XTRIM every INTERVAL, e.g. 30s, instead of on every XADD")
+            .long("trim-every")
+            .value_name("INTERVAL")
+            .takes_value(true),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The arguments of a command, e.g. `XADD s MAXLEN ~ 10 * n 1`
+    fn text(cmd: &Cmd) -> String {
+        let args: Vec<String> = cmd
+            .args_iter()
+            .map(|arg| match arg {
+                redis::Arg::Simple(bytes) => String::from_utf8_lossy(bytes).into_owned(),
+                redis::Arg::Cursor => "0".to_string(),
+            })
+            .collect();
+        args.join(" ")
+    }
+
+    fn retention(args: &[&str]) -> std::result::Result<Retention, ConfigError> {
+        let matches = retention_args(App::new("producer")).try_get_matches_from(args).unwrap();
+        Retention::from_matches(&matches)
+    }
+
+    #[test]
+    fn test_parse_duration() {
+        assert_eq!(parse_duration("10m"), Some(Duration::from_secs(600)));
+        assert_eq!(parse_duration("90"), Some(Duration::from_secs(90)));
+        assert_eq!(parse_duration("250ms"), Some(Duration::from_millis(250)));
+        assert_eq!(parse_duration("1d"), Some(Duration::from_secs(86400)));
+        assert_eq!(parse_duration("m"), None);
+        assert_eq!(parse_duration("10 minutes"), None);
+    }
+
+    #[test]
+    fn test_from_matches() {
+        assert_eq!(retention(&["producer"]).unwrap(), Retention::default());
+        assert_eq!(retention(&["producer", "--maxlen", "~1000"]).unwrap(), Retention::new(Limit::MaxLen(1000)).approximate(true));
+        assert_eq!(
+            retention(&["producer", "--min-id", "1700000000000-0", "--trim-every", "30s"]).unwrap(),
+            Retention::new(Limit::MinId(StreamId::new(1_700_000_000_000, 0))).every(Duration::from_secs(30))
+        );
+        assert_eq!(retention(&["producer", "--max-age", "=10m"]).unwrap().limit(), Some(Limit::MaxAge(Duration::from_secs(600))));
+        assert!(retention(&["producer", "--maxlen", "lots"]).is_err());
+        assert!(retention(&["producer", "--trim-every", "30s"]).is_err());
+    }
+
+    #[test]
+    fn test_commands() {
+        let items = [("n", "1")];
+        assert_eq!(text(&Retention::default().xadd("s", &items)), "XADD s * n 1");
+        assert!(Retention::default().xtrim("s").is_none());
+
+        let maxlen = Retention::new(Limit::MaxLen(10)).approximate(true);
+        assert_eq!(text(&maxlen.xadd("s", &items)), "XADD s MAXLEN ~ 10 * n 1");
+        assert_eq!(text(&maxlen.xtrim("s").unwrap()), "XTRIM s MAXLEN ~ 10");
+        // Without the `=` that Redis 5 and 6.0 don't know
+        assert_eq!(text(&Retention::new(Limit::MaxLen(10)).xadd("s", &items)), "XADD s MAXLEN 10 * n 1");
+
+        // A minute ago, give or take the time the test takes
+        let age = Retention::new(Limit::MaxAge(Duration::from_secs(60)));
+        let trim = text(&age.xtrim("s").unwrap());
+        let min_id: StreamId = trim.rsplit(' ').next().unwrap().parse().unwrap();
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_millis() as u64;
+        assert!((now - 61_000..=now - 60_000).contains(&min_id.ms), "{}", trim);
+        assert!(trim.starts_with("XTRIM s MINID = "));
+    }
+
+    #[test]
+    fn test_periodic_trim() {
+        let mut retention = Retention::new(Limit::MaxLen(10)).every(Duration::from_secs(3600));
+        // The limit is left to XTRIM
+        assert_eq!(text(&retention.xadd("s", &[("n", "1")])), "XADD s * n 1");
+        assert!(retention.trim_due("s").is_some());
+        assert!(retention.trim_due("s").is_none());
+    }
+}
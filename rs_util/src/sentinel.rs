@@ -0,0 +1,64 @@
+use std::time::Duration;
+
+use redis::{Connection, ConnectionAddr, ConnectionInfo, ErrorKind, FromRedisValue, RedisResult, Value};
+
+use crate::SentinelConfig;
+
+/// How long to wait for a Sentinel before asking the next one
+const SENTINEL_TIMEOUT: Duration = Duration::from_millis(500);
+
+/// Ask the Sentinels, in order, for the address of the current master.
+/// The first Sentinel that answers wins, so a Sentinel that is down only costs a timeout.
+pub fn master_addr(sentinel: &SentinelConfig) -> RedisResult<(String, u16)> {
+    let mut last_err = None;
+    for (host, port) in &sentinel.addresses {
+        match ask_sentinel(host, *port, &sentinel.master_name) {
+            Ok(Some(addr)) => return Ok(addr),
+            Ok(None) => {
+                last_err = Some((
+                    ErrorKind::InvalidClientConfig,
+                    "Sentinel does not know the master",
+                    format!("{} at {}:{}", sentinel.master_name, host, port),
+                ).into())
+            }
+            Err(e) => last_err = Some(e),
+        }
+    }
+    Err(last_err.unwrap_or_else(|| (ErrorKind::EmptySentinelList, "No sentinel addresses configured").into()))
+}
+
+fn ask_sentinel(host: &str, port: u16, master_name: &str) -> RedisResult<Option<(String, u16)>> {
+    let client = redis::Client::open(ConnectionInfo {
+        addr: ConnectionAddr::Tcp(host.to_string(), port),
+        redis: Default::default(),
+    })?;
+    let mut con = client.get_connection_with_timeout(SENTINEL_TIMEOUT)?;
+    con.set_read_timeout(Some(SENTINEL_TIMEOUT))?;
+    redis::cmd("SENTINEL").arg("GET-MASTER-ADDR-BY-NAME").arg(master_name).query(&mut con)
+}
+
+/// Make sure the server really is the master.
+/// Right after a failover, a Sentinel may still hand out the address of the old master,
+/// which comes back as a replica.
+pub(crate) fn check_master(con: &mut Connection) -> RedisResult<()> {
+    check_role(redis::cmd("ROLE").query(con)?)
+}
+
+/// The async twin of `check_master`
+pub(crate) async fn check_master_async<C: redis::aio::ConnectionLike>(con: &mut C) -> RedisResult<()> {
+    check_role(redis::cmd("ROLE").query_async(con).await?)
+}
+
+/// Check the reply to ROLE, whose first element is the role of the server
+fn check_role(role: Vec<Value>) -> RedisResult<()> {
+    let role = match role.first() {
+        Some(role) => String::from_redis_value(role)?,
+        None => String::new(),
+    };
+    if role == "master" {
+        Ok(())
+    } else {
+        // The same error as writing to a replica, so that callers reconnect on either
+        Err((ErrorKind::ReadOnly, "Server is not the master", role).into())
+    }
+}
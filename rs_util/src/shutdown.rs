@@ -0,0 +1,133 @@
+use std::io;
+use std::process::{Child, ExitStatus};
+use std::sync::{Arc, Condvar, Mutex, OnceLock};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// A request to stop, shared between threads.  Loops check it between entries, so that the
+/// entry at hand is finished and acknowledged before the process exits.
+#[derive(Clone, Debug, Default)]
+pub struct Shutdown {
+    state: Arc<(Mutex<bool>, Condvar)>,
+}
+
+impl Shutdown {
+    pub fn new() -> Shutdown {
+        Shutdown::default()
+    }
+
+    /// Ask everything holding this token to stop
+    pub fn trigger(&self) {
+        let (triggered, changed) = &*self.state;
+        *triggered.lock().unwrap() = true;
+        changed.notify_all();
+    }
+
+    pub fn is_triggered(&self) -> bool {
+        *self.state.0.lock().unwrap()
+    }
+
+    /// Sleep for `duration`, waking up early if shutdown is requested.
+    /// Returns whether shutdown was requested, e.g. `if shutdown.sleep(delay) { break; }`
+    pub fn sleep(&self, duration: Duration) -> bool {
+        let (triggered, changed) = &*self.state;
+        let guard = triggered.lock().unwrap();
+        let (guard, _) = changed.wait_timeout_while(guard, duration, |triggered| !*triggered).unwrap();
+        *guard
+    }
+
+    /// Block until shutdown is requested
+    pub fn wait(&self) {
+        let (triggered, changed) = &*self.state;
+        let _guard = changed.wait_while(triggered.lock().unwrap(), |triggered| !*triggered).unwrap();
+    }
+}
+
+/// The token of this process, triggered by SIGINT (Ctrl-C) or SIGTERM.
+/// The first call installs the signal handlers.  A second signal ends the process right away,
+/// for when finishing the current entry takes too long.
+pub fn shutdown_token() -> Shutdown {
+    static TOKEN: OnceLock<Shutdown> = OnceLock::new();
+    TOKEN
+        .get_or_init(|| {
+            let token = Shutdown::new();
+            #[cfg(unix)]
+            listen(token.clone());
+            token
+        })
+        .clone()
+}
+
+#[cfg(unix)]
+fn listen(token: Shutdown) {
+    use signal_hook::consts::{SIGINT, SIGTERM};
+    use signal_hook::iterator::Signals;
+
+    let mut signals = match Signals::new([SIGINT, SIGTERM]) {
+        Ok(signals) => signals,
+        Err(e) => {
+            tracing::warn!(error = %e, "Could not install the signal handlers; Ctrl-C stops the process right away");
+            return;
+        }
+    };
+    thread::spawn(move || {
+        for signal in signals.forever() {
+            if token.is_triggered() {
+                tracing::warn!(signal, "Second signal, exiting now");
+                std::process::exit(128 + signal);
+            }
+            tracing::info!(signal, "Shutting down after the current entry; send the signal again to exit now");
+            token.trigger();
+        }
+    });
+}
+
+/// Ask a child process to stop with SIGTERM, so it can finish its current entry, and kill it
+/// if it hasn't exited after `grace`.  Elsewhere than on Unix the child is killed right away.
+pub fn terminate(child: &mut Child, grace: Duration) -> io::Result<ExitStatus> {
+    #[cfg(unix)]
+    {
+        // The child has not been waited for, so the PID still belongs to it
+        if unsafe { libc::kill(child.id() as libc::pid_t, libc::SIGTERM) } == 0 {
+            let deadline = Instant::now() + grace;
+            while Instant::now() < deadline {
+                if let Some(status) = child.try_wait()? {
+                    return Ok(status);
+                }
+                thread::sleep(Duration::from_millis(50));
+            }
+        }
+    }
+    child.kill()?;
+    child.wait()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sleep_wakes_up() {
+        let shutdown = Shutdown::new();
+        assert!(!shutdown.sleep(Duration::from_millis(10)));
+
+        let other = shutdown.clone();
+        let handle = thread::spawn(move || other.sleep(Duration::from_secs(30)));
+        thread::sleep(Duration::from_millis(20));
+        let start = Instant::now();
+        shutdown.trigger();
+        assert!(handle.join().unwrap());
+        assert!(start.elapsed() < Duration::from_secs(5));
+        assert!(shutdown.is_triggered() && shutdown.sleep(Duration::from_secs(30)));
+        shutdown.wait();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_terminate() {
+        let mut child = std::process::Command::new("sleep").arg("30").spawn().unwrap();
+        let status = terminate(&mut child, Duration::from_secs(5)).unwrap();
+        // sleep has no handler, so SIGTERM ends it
+        assert!(!status.success());
+    }
+}
@@ -0,0 +1,236 @@
+use std::error;
+use std::fmt;
+use std::str::FromStr;
+
+use redis::{ErrorKind, FromRedisValue, RedisError, RedisResult, RedisWrite, ToRedisArgs, Value};
+
+/// A complete Stream entry ID: `<millisecondsTime>-<sequenceNumber>`
+///
+/// IDs are ordered first by their millisecond time and then by their sequence number,
+/// which is exactly the order in which Redis stores entries in a Stream.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct StreamId {
+    pub ms: u64,
+    pub seq: u64,
+}
+
+impl StreamId {
+    /// `0-0`, which is never a valid entry ID, but is accepted as a starting point by XREAD and XGROUP
+    pub const ZERO: StreamId = StreamId { ms: 0, seq: 0 };
+    /// `0-1`, the lowest valid full entry ID in a Stream
+    pub const MIN: StreamId = StreamId { ms: 0, seq: 1 };
+    /// The highest possible entry ID
+    pub const MAX: StreamId = StreamId { ms: u64::MAX, seq: u64::MAX };
+
+    pub fn new(ms: u64, seq: u64) -> StreamId {
+        StreamId { ms, seq }
+    }
+
+    /// The ID immediately following this one, or `None` if this is already `StreamId::MAX`
+    pub fn next(&self) -> Option<StreamId> {
+        match self.seq.checked_add(1) {
+            Some(seq) => Some(StreamId { ms: self.ms, seq }),
+            None => self.ms.checked_add(1).map(|ms| StreamId { ms, seq: 0 }),
+        }
+    }
+
+    /// The ID immediately preceding this one, or `None` if this is already `StreamId::ZERO`
+    pub fn prev(&self) -> Option<StreamId> {
+        match self.seq.checked_sub(1) {
+            Some(seq) => Some(StreamId { ms: self.ms, seq }),
+            None => self.ms.checked_sub(1).map(|ms| StreamId { ms, seq: u64::MAX }),
+        }
+    }
+}
+
+impl fmt::Display for StreamId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}-{}", self.ms, self.seq)
+    }
+}
+
+/// The error returned when a string is not a valid Stream entry ID
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParseStreamIdError {
+    input: String,
+}
+
+impl fmt::Display for ParseStreamIdError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid stream entry id: {:?}", self.input)
+    }
+}
+
+impl error::Error for ParseStreamIdError {}
+
+impl FromStr for StreamId {
+    type Err = ParseStreamIdError;
+
+    /// Parse `<ms>-<seq>` or just `<ms>`, in which case the sequence number is 0,
+    /// just like Redis does for the start of a range.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let err = || ParseStreamIdError { input: s.to_string() };
+        let (ms, seq) = match s.split_once('-') {
+            Some((ms, seq)) => (ms, seq.parse().map_err(|_| err())?),
+            None => (s, 0),
+        };
+        Ok(StreamId { ms: ms.parse().map_err(|_| err())?, seq })
+    }
+}
+
+impl ToRedisArgs for StreamId {
+    fn write_redis_args<W>(&self, out: &mut W)
+    where
+        W: ?Sized + RedisWrite,
+    {
+        out.write_arg(self.to_string().as_bytes())
+    }
+}
+
+impl FromRedisValue for StreamId {
+    fn from_redis_value(v: &Value) -> RedisResult<StreamId> {
+        let s: String = FromRedisValue::from_redis_value(v)?;
+        s.parse().map_err(|e: ParseStreamIdError| {
+            RedisError::from((ErrorKind::TypeError, "Response was not a stream entry id", e.to_string()))
+        })
+    }
+}
+
+/// Any ID accepted by the Stream commands, including the special IDs
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum EntryId {
+    /// A complete entry ID, e.g. `1643414204175-0`
+    Id(StreamId),
+    /// `-`: the lowest ID in a Stream (XRANGE, XREVRANGE)
+    Min,
+    /// `+`: the highest ID in a Stream (XRANGE, XREVRANGE)
+    Max,
+    /// `$`: the ID of the last entry in the Stream (XREAD, XGROUP)
+    Last,
+    /// `>`: entries never delivered to any consumer of the group (XREADGROUP)
+    Undelivered,
+    /// `*`: let the server generate the whole ID (XADD)
+    Auto,
+    /// `<ms>-*`: let the server generate the sequence number for the given time (XADD, Redis 7+)
+    AutoSeq(u64),
+}
+
+impl EntryId {
+    /// `0`, which asks XREADGROUP for a consumer's pending entries and XGROUP for the whole Stream
+    pub const ZERO: EntryId = EntryId::Id(StreamId::ZERO);
+}
+
+impl From<StreamId> for EntryId {
+    fn from(id: StreamId) -> EntryId {
+        EntryId::Id(id)
+    }
+}
+
+impl fmt::Display for EntryId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EntryId::Id(id) => id.fmt(f),
+            EntryId::Min => f.write_str("-"),
+            EntryId::Max => f.write_str("+"),
+            EntryId::Last => f.write_str("$"),
+            EntryId::Undelivered => f.write_str(">"),
+            EntryId::Auto => f.write_str("*"),
+            EntryId::AutoSeq(ms) => write!(f, "{}-*", ms),
+        }
+    }
+}
+
+impl FromStr for EntryId {
+    type Err = ParseStreamIdError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "-" => Ok(EntryId::Min),
+            "+" => Ok(EntryId::Max),
+            "$" => Ok(EntryId::Last),
+            ">" => Ok(EntryId::Undelivered),
+            "*" => Ok(EntryId::Auto),
+            _ => match s.strip_suffix("-*") {
+                Some(ms) => ms
+                    .parse()
+                    .map(EntryId::AutoSeq)
+                    .map_err(|_| ParseStreamIdError { input: s.to_string() }),
+                None => s.parse().map(EntryId::Id),
+            },
+        }
+    }
+}
+
+impl ToRedisArgs for EntryId {
+    fn write_redis_args<W>(&self, out: &mut W)
+    where
+        W: ?Sized + RedisWrite,
+    {
+        out.write_arg(self.to_string().as_bytes())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_and_display() {
+        let id: StreamId = "1643414204175-3".parse().unwrap();
+        assert_eq!(id, StreamId::new(1643414204175, 3));
+        assert_eq!(id.to_string(), "1643414204175-3");
+        assert_eq!("1643414204175".parse::<StreamId>().unwrap(), StreamId::new(1643414204175, 0));
+        assert!("1643414204175-".parse::<StreamId>().is_err());
+        assert!("-1-0".parse::<StreamId>().is_err());
+        assert!("abc".parse::<StreamId>().is_err());
+    }
+
+    #[test]
+    fn test_ordering() {
+        assert!(StreamId::new(1, u64::MAX) < StreamId::new(2, 0));
+        assert!(StreamId::new(2, 1) > StreamId::new(2, 0));
+        assert!(StreamId::ZERO < StreamId::MIN);
+    }
+
+    #[test]
+    fn test_next() {
+        assert_eq!(StreamId::new(1643414204175, 0).next(), Some(StreamId::new(1643414204175, 1)));
+    }
+
+    #[test]
+    fn test_next_max_seq() {
+        let id = StreamId::new(1643414204175, u64::MAX);
+        assert_eq!(id.next(), Some(StreamId::new(1643414204176, 0)));
+        assert_eq!(StreamId::MAX.next(), None);
+    }
+
+    #[test]
+    fn test_prev() {
+        assert_eq!(StreamId::new(1643414204175, 23).prev(), Some(StreamId::new(1643414204175, 22)));
+    }
+
+    #[test]
+    fn test_prev_zero_seq() {
+        let id = StreamId::new(1643414204175, 0);
+        assert_eq!(id.prev(), Some(StreamId::new(1643414204174, u64::MAX)));
+        assert_eq!(StreamId::ZERO.prev(), None);
+    }
+
+    #[test]
+    fn test_special_ids() {
+        for special in ["-", "+", "$", ">", "*", "1643414204175-*", "0-0", "5-7"] {
+            assert_eq!(special.parse::<EntryId>().unwrap().to_string(), special);
+        }
+        assert_eq!("0".parse::<EntryId>().unwrap(), EntryId::ZERO);
+        assert!("x-*".parse::<EntryId>().is_err());
+    }
+
+    #[test]
+    fn test_redis_value() {
+        let v = Value::Data(b"1643414204175-1".to_vec());
+        assert_eq!(StreamId::from_redis_value(&v).unwrap(), StreamId::new(1643414204175, 1));
+        assert!(StreamId::from_redis_value(&Value::Data(b"nope".to_vec())).is_err());
+        assert_eq!(StreamId::new(1, 2).to_redis_args(), vec![b"1-2".to_vec()]);
+        assert_eq!(EntryId::Undelivered.to_redis_args(), vec![b">".to_vec()]);
+    }
+}
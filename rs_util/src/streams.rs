@@ -0,0 +1,809 @@
+//! A consumer group member that does the bookkeeping around the processing of the entries:
+//! it recovers its pending entries after a restart, then reads new ones, acknowledges what was
+//! processed, takes over the entries other consumers left idle, moves the entries it can't
+//! handle to a dead-letter stream and stops when the stream stays idle or on shutdown.
+//!
+//! ```no_run
+//! use rs_util::streams::{Ack, GroupWorker};
+//!
+//! # fn main() -> rs_util::Result<()> {
+//! # let config = rs_util::Config::default();
+//! let mut con = rs_util::connect(&config)?;
+//! let worker = GroupWorker::new("numbers", "primes", "BOB-01").batch_size(5);
+//! worker.create_group(&mut con)?;
+//! worker.run(&mut con, &mut |entry: &redis::streams::StreamId| {
+//!     let n: i64 = rs_util::entry_field(entry, "n")?;
+//!     println!("{} is {}", entry.id, n);
+//!     Ok(Ack::Ack)
+//! })?;
+//! # Ok(())
+//! # }
+//! ```
+
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use redis::streams::{StreamClaimReply, StreamId as Entry, StreamPendingCountReply, StreamReadOptions, StreamReadReply};
+use redis::{Commands, ConnectionLike, ErrorKind, RedisError, RedisResult, Value};
+use tracing::{debug, info, info_span, warn};
+
+use crate::dlq::{self, DeadLetter};
+use crate::{metrics, Context, EntryId, Error, Feature, Result, Shutdown, StreamId};
+
+/// How many of the oldest pending entries XPENDING lists, when claiming without XAUTOCLAIM
+const PENDING_SCAN: usize = 100;
+
+/// What to do with an entry once the handler is done with it
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Ack {
+    /// It was processed: acknowledge it, as the `AckMode` says
+    Ack,
+    /// Leave it pending, e.g. to be retried or claimed by another consumer later
+    Keep,
+}
+
+/// Processes the entries a `GroupWorker` reads.  Any `FnMut(&Entry) -> Result<Ack>` is one.
+pub trait Handler {
+    /// Process one entry.  An error stops the worker; the entry stays pending.  With a dead-letter
+    /// stream, an entry whose fields don't decode is moved there instead, see `GroupWorker::dead_letter`.
+    fn handle(&mut self, entry: &Entry) -> Result<Ack>;
+}
+
+impl<F: FnMut(&Entry) -> Result<Ack>> Handler for F {
+    fn handle(&mut self, entry: &Entry) -> Result<Ack> {
+        self(entry)
+    }
+}
+
+/// When the entries the handler is done with are acknowledged
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AckMode {
+    /// With one XACK per entry, right after it was handled
+    PerEntry,
+    /// With one XACK for all the entries of a read, once they were handled
+    PerBatch,
+    /// Never, the entries stay pending.  For consumers that only observe the stream.
+    Never,
+}
+
+/// What to do while no new entries arrive
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum IdlePolicy {
+    /// Keep waiting, one block time after another
+    Wait,
+    /// Double the block time after every read that timed out, and stop after `retries` of them
+    /// in a row.  Entries arriving in between start over with the configured block time.
+    Backoff { retries: u32 },
+}
+
+/// What a worker did, when it stopped
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct WorkerStats {
+    /// Entries that were pending from before the worker started, handled again
+    pub recovered: usize,
+    /// Entries taken over from other consumers, see `GroupWorker::claim_idle`
+    pub claimed: usize,
+    /// Entries handled, including the recovered and claimed ones
+    pub handled: usize,
+    /// Entries acknowledged
+    pub acked: usize,
+    /// Entries moved to the dead-letter stream, see `GroupWorker::dead_letter`
+    pub dead_lettered: usize,
+    /// Pending entries that were deleted from the stream before they were handled, e.g. by
+    /// trimming, and acknowledged so they don't stay pending forever
+    pub deleted: usize,
+}
+
+/// A member of a consumer group.  It first handles the entries that were delivered to it before
+/// but never acknowledged, e.g. because it crashed (reading from ID `0`), then new entries
+/// (reading from `>`).  See the module documentation for an example.
+#[derive(Clone, Debug)]
+pub struct GroupWorker {
+    stream: String,
+    group: String,
+    consumer: String,
+    batch_size: usize,
+    block: Duration,
+    idle: IdlePolicy,
+    ack_mode: AckMode,
+    recover_pending: bool,
+    claim: Option<Claim>,
+    max_deliveries: Option<usize>,
+    shutdown: Option<Shutdown>,
+}
+
+/// See `GroupWorker::claim_idle`
+#[derive(Clone, Copy, Debug)]
+struct Claim {
+    min_idle: Duration,
+    every: Duration,
+}
+
+impl GroupWorker {
+    /// A worker reading 10 entries at a time, blocking for up to 5 seconds, waiting forever for
+    /// new entries and acknowledging each entry right after it was handled
+    pub fn new(stream: &str, group: &str, consumer: &str) -> GroupWorker {
+        GroupWorker {
+            stream: stream.to_string(),
+            group: group.to_string(),
+            consumer: consumer.to_string(),
+            batch_size: 10,
+            block: Duration::from_secs(5),
+            idle: IdlePolicy::Wait,
+            ack_mode: AckMode::PerEntry,
+            recover_pending: true,
+            claim: None,
+            max_deliveries: None,
+            shutdown: None,
+        }
+    }
+
+    /// The most entries to read at a time
+    pub fn batch_size(mut self, batch_size: usize) -> Self {
+        self.batch_size = batch_size.max(1);
+        self
+    }
+
+    /// How long a read waits for new entries
+    pub fn block(mut self, block: Duration) -> Self {
+        self.block = block;
+        self
+    }
+
+    pub fn idle(mut self, idle: IdlePolicy) -> Self {
+        self.idle = idle;
+        self
+    }
+
+    pub fn ack_mode(mut self, ack_mode: AckMode) -> Self {
+        self.ack_mode = ack_mode;
+        self
+    }
+
+    /// Whether to handle the entries that are still pending for this consumer first [default: true]
+    pub fn recover_pending(mut self, recover_pending: bool) -> Self {
+        self.recover_pending = recover_pending;
+        self
+    }
+
+    /// Every `every`, take over a batch of the entries that were delivered to a consumer of the
+    /// group but not acknowledged for at least `min_idle`, e.g. because that consumer crashed and
+    /// never came back.  `min_idle` has to be longer than handling a batch takes, or entries are
+    /// taken away from consumers that are still working on them.
+    /// Uses XAUTOCLAIM, or XPENDING and XCLAIM before Redis 6.2.
+    pub fn claim_idle(mut self, min_idle: Duration, every: Duration) -> Self {
+        self.claim = Some(Claim { min_idle, every });
+        self
+    }
+
+    /// Move the entries that can't be handled to the dead-letter stream of the stream,
+    /// `dlq::key(stream)`, and acknowledge them: the entries whose fields don't decode, and the
+    /// entries that were already delivered `max_deliveries` times, e.g. because handling them
+    /// crashed the consumer every time.
+    pub fn dead_letter(mut self, max_deliveries: usize) -> Self {
+        self.max_deliveries = Some(max_deliveries);
+        self
+    }
+
+    /// Stop between two entries once `shutdown` is triggered, e.g. by `rs_util::shutdown_token()`.
+    /// A blocking read is not interrupted, so that takes up to the block time.
+    pub fn shutdown(mut self, shutdown: Shutdown) -> Self {
+        self.shutdown = Some(shutdown);
+        self
+    }
+
+    pub fn stream(&self) -> &str {
+        &self.stream
+    }
+
+    pub fn group(&self) -> &str {
+        &self.group
+    }
+
+    pub fn consumer(&self) -> &str {
+        &self.consumer
+    }
+
+    /// Create the group, starting at the beginning of the stream, unless it exists.
+    /// Returns whether it was created.  Fails if the stream doesn't exist.
+    pub fn create_group<C: ConnectionLike>(&self, con: &mut C) -> Result<bool> {
+        // Consumer groups need Redis 5.0.  Say so, instead of mistaking "unknown command" for an existing group.
+        crate::capabilities(con)?.require(Feature::Streams)?;
+        let result: RedisResult<String> = con.xgroup_create(&self.stream, &self.group, EntryId::ZERO);
+        match result {
+            Ok(_) => Ok(true),
+            Err(e) if e.code() == Some("BUSYGROUP") => Ok(false),
+            Err(e) => Err(Error::from(e).context(format!("creating group {} on stream {}", self.group, self.stream))),
+        }
+    }
+
+    fn shutting_down(&self) -> bool {
+        self.shutdown.as_ref().is_some_and(Shutdown::is_triggered)
+    }
+
+    /// Read and handle entries until the idle policy or a shutdown says to stop
+    pub fn run<C: ConnectionLike, H: Handler>(&self, con: &mut C, handler: &mut H) -> Result<WorkerStats> {
+        let _consumer = info_span!("consumer", stream = %self.stream, group = %self.group, name = %self.consumer).entered();
+        let mut stats = WorkerStats::default();
+        // While recovering, the reads go through this consumer's pending entries, from one batch
+        // to the next, since entries that stay pending would come back on every read from 0
+        let mut recovering = self.recover_pending;
+        let mut from = if recovering { EntryId::ZERO } else { EntryId::Undelivered };
+        let mut block = self.block;
+        let mut idle_reads = 0;
+        // XAUTOCLAIM goes through the pending entries with a cursor, from one claim to the next
+        let autoclaim = match self.claim {
+            Some(_) => crate::capabilities(con)?.supports(Feature::XAutoClaim),
+            None => false,
+        };
+        let mut cursor = StreamId::ZERO;
+        let mut next_claim = Instant::now();
+
+        while !self.shutting_down() {
+            // Other consumers' entries are only claimed once this consumer's own are recovered
+            if let (Some(claim), EntryId::Undelivered) = (self.claim, from) {
+                if Instant::now() >= next_claim {
+                    next_claim = Instant::now() + claim.every;
+                    let (entries, deleted) = self
+                        .claim(con, claim.min_idle, autoclaim, &mut cursor)
+                        .with_context(|| format!("claiming idle entries of stream {}", self.stream))?;
+                    self.forget_deleted(con, &deleted, &mut stats)?;
+                    if !entries.is_empty() {
+                        info!(count = entries.len(), "Claimed idle entries");
+                        stats.claimed += entries.len();
+                        metrics().claimed(&self.stream, &self.group, entries.len());
+                        block = self.block;
+                        idle_reads = 0;
+                        self.handle_batch(con, handler, &entries, true, &mut stats)?;
+                        continue;
+                    }
+                }
+            }
+
+            let opts = StreamReadOptions::default()
+                .group(&self.group, &self.consumer)
+                .count(self.batch_size)
+                .block(block.as_millis() as usize);
+            let reply: StreamReadReply = match con.xread_options(&[&self.stream], &[from], &opts) {
+                Ok(reply) => reply,
+                // e.g. a ResilientConnection that gave up reconnecting because of the shutdown
+                Err(_) if self.shutting_down() => break,
+                Err(e) => return Err(Error::from(e).context(format!("reading from stream {}", self.stream))),
+            };
+
+            match reply.keys.into_iter().next().map(|key| key.ids) {
+                // The read timed out
+                None => {
+                    if let IdlePolicy::Backoff { retries } = self.idle {
+                        if idle_reads == retries {
+                            info!("Waited long enough, stopping");
+                            break;
+                        }
+                        idle_reads += 1;
+                        block *= 2;
+                    }
+                }
+                // Reading pending entries never blocks; an empty reply means none are left
+                Some(entries) if entries.is_empty() => {
+                    if recovering {
+                        info!(recovered = stats.recovered, "Processing new entries");
+                        recovering = false;
+                    }
+                    from = EntryId::Undelivered;
+                }
+                Some(entries) => {
+                    block = self.block;
+                    idle_reads = 0;
+                    if recovering {
+                        debug!(count = entries.len(), "Recovering pending entries");
+                        stats.recovered += entries.len();
+                        if let Some(last) = entries.last() {
+                            from = EntryId::Id(last.id.parse()?);
+                        }
+                    }
+                    self.handle_batch(con, handler, &entries, recovering, &mut stats)?;
+                }
+            }
+        }
+        if self.shutting_down() {
+            info!("Shutdown requested, stopping");
+        }
+        Ok(stats)
+    }
+
+    /// Take over up to a batch of the entries that were idle for `min_idle`.  Returns them, and
+    /// the IDs of the idle entries that were deleted from the stream.
+    fn claim<C: ConnectionLike>(
+        &self,
+        con: &mut C,
+        min_idle: Duration,
+        autoclaim: bool,
+        cursor: &mut StreamId,
+    ) -> RedisResult<(Vec<Entry>, Vec<String>)> {
+        let min_idle_ms = min_idle.as_millis() as usize;
+        if autoclaim {
+            // The reply is the cursor to go on from, the claimed entries and, since Redis 7.0,
+            // the IDs of the pending entries that were deleted from the stream in the meantime,
+            // which XAUTOCLAIM already removed from the pending entries.  Redis 6.2 answers them
+            // as entries without fields instead, see `handle_batch`.
+            let reply: Vec<Value> = redis::cmd("XAUTOCLAIM")
+                .arg(&self.stream)
+                .arg(&self.group)
+                .arg(&self.consumer)
+                .arg(min_idle_ms)
+                .arg(EntryId::from(*cursor))
+                .arg("COUNT")
+                .arg(self.batch_size)
+                .query(con)?;
+            return match reply.as_slice() {
+                [next, entries, deleted @ ..] => {
+                    *cursor = redis::from_redis_value(next)?;
+                    let deleted = match deleted.first() {
+                        Some(ids) => redis::from_redis_value(ids)?,
+                        None => vec![],
+                    };
+                    Ok((redis::from_redis_value::<StreamClaimReply>(entries)?.ids, deleted))
+                }
+                _ => Err(RedisError::from((ErrorKind::TypeError, "Unexpected XAUTOCLAIM reply"))),
+            };
+        }
+
+        // Before Redis 6.2, XPENDING can't filter by idle time: look at the oldest pending entries
+        // and claim the idle ones.  XCLAIM checks the idle time again, in case they were just acknowledged.
+        let pending: StreamPendingCountReply = con.xpending_count(&self.stream, &self.group, "-", "+", PENDING_SCAN)?;
+        let ids: Vec<&str> = pending
+            .ids
+            .iter()
+            .filter(|pending| pending.last_delivered_ms >= min_idle_ms)
+            .take(self.batch_size)
+            .map(|pending| pending.id.as_str())
+            .collect();
+        if ids.is_empty() {
+            return Ok((vec![], vec![]));
+        }
+        let reply: Vec<Value> = con.xclaim(&self.stream, &self.group, &self.consumer, min_idle_ms, &ids)?;
+        let claimed = redis::from_redis_value::<StreamClaimReply>(&Value::Bulk(reply.clone()))?.ids;
+        if !reply.contains(&Value::Nil) {
+            return Ok((claimed, vec![]));
+        }
+
+        // XCLAIM answers nil, without the ID, for an entry it claimed but that was deleted from the
+        // stream.  They are the IDs that are pending for this consumer now, but weren't in the reply.
+        let missing: Vec<&str> = ids.into_iter().filter(|id| !claimed.iter().any(|entry| entry.id == *id)).collect();
+        let mine = self.pending_deliveries(con, &missing)?;
+        let deleted = missing.into_iter().filter(|id| mine.contains_key(*id)).map(str::to_string).collect();
+        Ok((claimed, deleted))
+    }
+
+    /// Handle the entries of one read, stopping early on shutdown.  Entries that were not handled
+    /// stay pending, and are recovered the next time the consumer starts.
+    /// `redelivered` entries, i.e. recovered or claimed ones, may have been delivered before.
+    fn handle_batch<C: ConnectionLike, H: Handler>(
+        &self,
+        con: &mut C,
+        handler: &mut H,
+        entries: &[Entry],
+        redelivered: bool,
+        stats: &mut WorkerStats,
+    ) -> Result<()> {
+        let read_at = Instant::now();
+        metrics().consumed(&self.stream, &self.group, entries.len());
+        let mut handled: Vec<&str> = vec![];
+        // XADD needs at least one field: an entry without any is a pending entry that was deleted
+        let (deleted, entries): (Vec<&Entry>, Vec<&Entry>) = entries.iter().partition(|entry| entry.map.is_empty());
+        let deleted: Vec<String> = deleted.into_iter().map(|entry| entry.id.clone()).collect();
+        self.forget_deleted(con, &deleted, stats)?;
+        let deliveries = match self.max_deliveries {
+            Some(_) if redelivered => {
+                let ids: Vec<&str> = entries.iter().map(|entry| entry.id.as_str()).collect();
+                self.pending_deliveries(con, &ids)
+                    .with_context(|| format!("counting the deliveries of entries of stream {}", self.stream))?
+            }
+            _ => HashMap::new(),
+        };
+
+        for entry in entries {
+            if self.shutting_down() {
+                break;
+            }
+            let _entry = info_span!("entry", id = %entry.id).entered();
+            let delivered = deliveries.get(&entry.id).copied().unwrap_or(1);
+            if self.max_deliveries.is_some_and(|max| delivered > max) {
+                self.dead_letter_entry(con, entry, delivered, &format!("delivered {} times", delivered), stats)?;
+                continue;
+            }
+            let ack = match handler.handle(entry) {
+                Ok(ack) => ack,
+                // An entry that doesn't decode never will, however many times it is delivered
+                Err(e) if self.max_deliveries.is_some() && is_bad_entry(&e) => {
+                    self.dead_letter_entry(con, entry, delivered, &e.to_string(), stats)?;
+                    continue;
+                }
+                Err(e) => {
+                    // What was handled before the failure is done
+                    self.ack(con, &handled, read_at, stats)?;
+                    return Err(e.context(format!("handling entry {}", entry.id)));
+                }
+            };
+            stats.handled += 1;
+            match (ack, self.ack_mode) {
+                (Ack::Ack, AckMode::PerEntry) => self.ack(con, &[&entry.id], read_at, stats)?,
+                (Ack::Ack, AckMode::PerBatch) => handled.push(&entry.id),
+                _ => metrics().observe_processing(&self.stream, &self.group, read_at.elapsed()),
+            }
+        }
+        self.ack(con, &handled, read_at, stats)
+    }
+
+    /// The IDs that are pending for this consumer, from the lowest to the highest of `ids`, and
+    /// how many times each was delivered
+    fn pending_deliveries<C: ConnectionLike>(&self, con: &mut C, ids: &[&str]) -> RedisResult<HashMap<String, usize>> {
+        let ids: Vec<StreamId> = ids.iter().filter_map(|id| id.parse().ok()).collect();
+        let (first, last) = match (ids.iter().min(), ids.iter().max()) {
+            (Some(first), Some(last)) => (EntryId::from(*first), EntryId::from(*last)),
+            _ => return Ok(HashMap::new()),
+        };
+        let pending: StreamPendingCountReply =
+            con.xpending_consumer_count(&self.stream, &self.group, first, last, PENDING_SCAN.max(ids.len()), &self.consumer)?;
+        Ok(pending.ids.into_iter().map(|pending| (pending.id, pending.times_delivered)).collect())
+    }
+
+    /// Acknowledge the pending entries that were deleted from the stream, whatever the `AckMode`:
+    /// there is nothing left to handle, and nobody else could ever acknowledge them
+    fn forget_deleted<C: ConnectionLike>(&self, con: &mut C, ids: &[String], stats: &mut WorkerStats) -> Result<()> {
+        if ids.is_empty() {
+            return Ok(());
+        }
+        warn!(count = ids.len(), ids = ?ids, "Pending entries were deleted from the stream before they were handled, e.g. by trimming");
+        let _: usize = con
+            .xack(&self.stream, &self.group, ids)
+            .with_context(|| format!("acknowledging {} deleted entries of stream {}", ids.len(), self.stream))?;
+        stats.deleted += ids.len();
+        metrics().deleted_pending(&self.stream, &self.group, ids.len());
+        Ok(())
+    }
+
+    /// Move an entry to the dead-letter stream, then acknowledge it.  This is no transaction,
+    /// which not every cluster connection can run: if the consumer stops in between, the entry
+    /// is moved again once it is delivered again.
+    fn dead_letter_entry<C: ConnectionLike>(
+        &self,
+        con: &mut C,
+        entry: &Entry,
+        deliveries: usize,
+        error: &str,
+        stats: &mut WorkerStats,
+    ) -> Result<()> {
+        warn!(deliveries, error, "Moving the entry to the dead-letter stream");
+        let letter = DeadLetter::new(&self.stream, &self.group, &self.consumer, entry, deliveries, error);
+        let _: String = letter
+            .add_cmd()
+            .query(con)
+            .with_context(|| format!("moving entry {} to {}", entry.id, dlq::key(&self.stream)))?;
+        let _: usize = con
+            .xack(&self.stream, &self.group, &[&entry.id])
+            .with_context(|| format!("acknowledging dead letter {} of stream {}", entry.id, self.stream))?;
+        stats.dead_lettered += 1;
+        metrics().dead_lettered(&self.stream, &self.group);
+        Ok(())
+    }
+
+    fn ack<C: ConnectionLike>(&self, con: &mut C, ids: &[&str], read_at: Instant, stats: &mut WorkerStats) -> Result<()> {
+        if ids.is_empty() {
+            return Ok(());
+        }
+        let acked: usize = con
+            .xack(&self.stream, &self.group, ids)
+            .with_context(|| format!("acknowledging {} entries of stream {}", ids.len(), self.stream))?;
+        stats.acked += acked;
+        metrics().acked(&self.stream, &self.group, acked);
+        for _ in ids {
+            metrics().observe_processing(&self.stream, &self.group, read_at.elapsed());
+        }
+        Ok(())
+    }
+}
+
+/// Whether the error is about the entry itself, rather than the server or the handler
+fn is_bad_entry(e: &Error) -> bool {
+    matches!(e.root(), Error::Decode(_) | Error::MissingField { .. })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::VecDeque;
+
+    use redis::Value;
+
+    use super::*;
+
+    /// Answers the commands with the given replies, in order, and remembers them
+    struct Replay {
+        replies: VecDeque<Value>,
+        sent: Vec<String>,
+    }
+
+    impl Replay {
+        fn new(replies: Vec<Value>) -> Replay {
+            Replay { replies: replies.into(), sent: vec![] }
+        }
+
+        /// The arguments of the commands that were sent, e.g. `XACK s g 1-0`
+        fn commands(&self) -> Vec<String> {
+            self.sent.clone()
+        }
+    }
+
+    impl ConnectionLike for Replay {
+        fn req_packed_command(&mut self, cmd: &[u8]) -> RedisResult<Value> {
+            // Keep the arguments of the RESP array, skipping the `*n` and `$len` lines
+            let text = String::from_utf8_lossy(cmd);
+            let args: Vec<&str> = text.split("\r\n").filter(|line| !line.is_empty()).skip(2).step_by(2).collect();
+            self.sent.push(args.join(" "));
+            Ok(self.replies.pop_front().expect("no reply left"))
+        }
+
+        fn req_packed_commands(&mut self, _cmd: &[u8], _offset: usize, _count: usize) -> RedisResult<Vec<Value>> {
+            Err((ErrorKind::ClientError, "pipelines are not replayed").into())
+        }
+
+        fn get_db(&self) -> i64 {
+            0
+        }
+
+        fn check_connection(&mut self) -> bool {
+            true
+        }
+
+        fn is_open(&self) -> bool {
+            true
+        }
+    }
+
+    fn data(s: &str) -> Value {
+        Value::Data(s.as_bytes().to_vec())
+    }
+
+    /// The entries `(id, n)`, as XCLAIM answers them
+    fn entries(ids: &[(&str, &str)]) -> Value {
+        Value::Bulk(ids.iter().map(|(id, n)| Value::Bulk(vec![data(id), Value::Bulk(vec![data("n"), data(n)])])).collect())
+    }
+
+    /// An XREADGROUP reply with entries `(id, n)`
+    fn read(ids: &[(&str, &str)]) -> Value {
+        Value::Bulk(vec![Value::Bulk(vec![data("s"), entries(ids)])])
+    }
+
+    fn sum(total: &mut i64) -> impl FnMut(&Entry) -> Result<Ack> + '_ {
+        move |entry| {
+            *total += crate::entry_field::<i64>(entry, "n")?;
+            Ok(Ack::Ack)
+        }
+    }
+
+    #[test]
+    fn test_recover_then_read_new_entries() {
+        let mut con = Replay::new(vec![
+            read(&[("1-0", "1")]), // pending
+            Value::Int(1),         // XACK
+            read(&[]),             // nothing pending anymore
+            read(&[("2-0", "2"), ("3-0", "3")]),
+            Value::Int(1),
+            Value::Int(1),
+            Value::Nil, // timed out
+            Value::Nil,
+        ]);
+        let mut total = 0;
+        let worker = GroupWorker::new("s", "g", "c").block(Duration::from_millis(100)).idle(IdlePolicy::Backoff { retries: 1 });
+        let stats = worker.run(&mut con, &mut sum(&mut total)).unwrap();
+
+        assert_eq!(total, 6);
+        assert_eq!(stats, WorkerStats { recovered: 1, handled: 3, acked: 3, ..WorkerStats::default() });
+        let commands = con.commands();
+        assert_eq!(commands[0], "XREADGROUP GROUP g c BLOCK 100 COUNT 10 STREAMS s 0-0");
+        assert_eq!(commands[1], "XACK s g 1-0");
+        assert_eq!(commands[3], "XREADGROUP GROUP g c BLOCK 100 COUNT 10 STREAMS s >");
+        // The block time doubles while the stream is idle
+        assert_eq!(commands[7], "XREADGROUP GROUP g c BLOCK 200 COUNT 10 STREAMS s >");
+        assert_eq!(commands.len(), 8);
+    }
+
+    #[test]
+    fn test_recover_entries_kept_pending() {
+        let mut con = Replay::new(vec![
+            read(&[("1-0", "1"), ("2-0", "2")]), // pending, and kept so
+            read(&[("3-0", "3")]),
+            read(&[]), // nothing pending after 3-0
+            Value::Nil,
+        ]);
+        let worker = GroupWorker::new("s", "g", "c").batch_size(2).idle(IdlePolicy::Backoff { retries: 0 });
+        let mut handler = |_: &Entry| Ok(Ack::Keep);
+        let stats = worker.run(&mut con, &mut handler).unwrap();
+
+        assert_eq!(stats, WorkerStats { recovered: 3, handled: 3, ..WorkerStats::default() });
+        let commands = con.commands();
+        assert_eq!(commands[1], "XREADGROUP GROUP g c BLOCK 5000 COUNT 2 STREAMS s 2-0");
+        assert_eq!(commands[2], "XREADGROUP GROUP g c BLOCK 5000 COUNT 2 STREAMS s 3-0");
+        assert_eq!(commands[3], "XREADGROUP GROUP g c BLOCK 5000 COUNT 2 STREAMS s >");
+        assert_eq!(commands.len(), 4);
+    }
+
+    #[test]
+    fn test_ack_per_batch_and_keep() {
+        let mut con = Replay::new(vec![read(&[("1-0", "1"), ("2-0", "2"), ("3-0", "3")]), Value::Int(2), Value::Nil]);
+        let worker = GroupWorker::new("s", "g", "c")
+            .recover_pending(false)
+            .ack_mode(AckMode::PerBatch)
+            .idle(IdlePolicy::Backoff { retries: 0 });
+        // Odd numbers are kept pending
+        let mut handler = |entry: &Entry| {
+            let n: i64 = crate::entry_field(entry, "n")?;
+            Ok(if n % 2 == 0 { Ack::Ack } else { Ack::Keep })
+        };
+        let stats = worker.run(&mut con, &mut handler).unwrap();
+        assert_eq!(stats.handled, 3);
+        assert_eq!(con.commands()[1], "XACK s g 2-0");
+    }
+
+    #[test]
+    fn test_handler_error_stops_the_worker() {
+        let mut con = Replay::new(vec![read(&[("1-0", "1"), ("2-0", "two"), ("3-0", "3")]), Value::Int(1)]);
+        let worker = GroupWorker::new("s", "g", "c").recover_pending(false).ack_mode(AckMode::PerBatch);
+        let mut total = 0;
+        let e = worker.run(&mut con, &mut sum(&mut total)).unwrap_err();
+        assert!(e.to_string().starts_with("handling entry 2-0: field 'n' of entry 2-0"), "{}", e);
+        // The entry before the failure is acknowledged, the rest stays pending
+        assert_eq!(con.commands().last().unwrap(), "XACK s g 1-0");
+    }
+
+    #[test]
+    fn test_shutdown() {
+        let shutdown = Shutdown::new();
+        let mut con = Replay::new(vec![read(&[("1-0", "1"), ("2-0", "2")]), Value::Int(1)]);
+        let worker = GroupWorker::new("s", "g", "c").recover_pending(false).shutdown(shutdown.clone());
+        let mut handler = |_: &Entry| {
+            shutdown.trigger();
+            Ok(Ack::Ack)
+        };
+        let stats = worker.run(&mut con, &mut handler).unwrap();
+        // The entry at hand is finished and acknowledged, the second one isn't touched
+        assert_eq!(stats, WorkerStats { handled: 1, acked: 1, ..WorkerStats::default() });
+    }
+
+    /// The replies of `capabilities` for a server of the given version
+    fn server(version: &str) -> Vec<Value> {
+        let info = data(&format!("# Server\r\nredis_version:{}\r\n", version));
+        vec![info, Value::Bulk(vec![Value::Bulk(vec![data("xautoclaim")])])]
+    }
+
+    #[test]
+    fn test_autoclaim() {
+        let mut replies = server("7.2.4");
+        replies.extend([
+            // The cursor, one entry and no deleted IDs
+            Value::Bulk(vec![data("5-0"), entries(&[("1-0", "1")]), Value::Bulk(vec![])]),
+            Value::Int(1),
+            Value::Nil,
+        ]);
+        let mut con = Replay::new(replies);
+        let worker = GroupWorker::new("s", "g", "c")
+            .recover_pending(false)
+            .claim_idle(Duration::from_secs(60), Duration::from_secs(10))
+            .idle(IdlePolicy::Backoff { retries: 0 });
+        let mut total = 0;
+        let stats = worker.run(&mut con, &mut sum(&mut total)).unwrap();
+        assert_eq!(stats, WorkerStats { claimed: 1, handled: 1, acked: 1, ..WorkerStats::default() });
+        let commands = con.commands();
+        assert_eq!(commands[2], "XAUTOCLAIM s g c 60000 0-0 COUNT 10");
+        assert_eq!(commands[3], "XACK s g 1-0");
+        // The next claim is only due in 10 seconds
+        assert!(commands[4].starts_with("XREADGROUP"));
+    }
+
+    #[test]
+    fn test_claim_before_redis_6_2() {
+        let pending = |id: &str, consumer: &str, idle: i64| {
+            Value::Bulk(vec![data(id), data(consumer), Value::Int(idle), Value::Int(1)])
+        };
+        let mut replies = server("6.0.16");
+        replies.extend([
+            Value::Bulk(vec![pending("1-0", "gone", 90_000), pending("2-0", "busy", 10), pending("3-0", "gone", 60_000)]),
+            entries(&[("1-0", "1"), ("3-0", "3")]),
+            Value::Int(1),
+            Value::Int(1),
+            Value::Nil,
+        ]);
+        let mut con = Replay::new(replies);
+        let worker = GroupWorker::new("s", "g", "c")
+            .recover_pending(false)
+            .claim_idle(Duration::from_secs(60), Duration::from_secs(10))
+            .idle(IdlePolicy::Backoff { retries: 0 });
+        let mut total = 0;
+        let stats = worker.run(&mut con, &mut sum(&mut total)).unwrap();
+        assert_eq!((stats.claimed, total), (2, 4));
+        let commands = con.commands();
+        assert_eq!(commands[2], "XPENDING s g - + 100");
+        // The entry that is only idle for 10 ms stays with its consumer
+        assert_eq!(commands[3], "XCLAIM s g c 60000 1-0 3-0");
+    }
+
+    #[test]
+    fn test_dead_letters() {
+        let pending = |id: &str, deliveries: i64| Value::Bulk(vec![data(id), data("c"), Value::Int(1000), Value::Int(deliveries)]);
+        let mut con = Replay::new(vec![
+            read(&[("1-0", "1"), ("2-0", "two"), ("3-0", "3")]), // pending
+            Value::Bulk(vec![pending("1-0", 3), pending("2-0", 1), pending("3-0", 2)]),
+            data("9-0"), // XADD to the dead-letter stream
+            Value::Int(1),
+            data("9-1"),
+            Value::Int(1),
+            Value::Int(1), // XACK 3-0
+            read(&[]),
+            Value::Nil,
+        ]);
+        let worker = GroupWorker::new("s", "g", "c").dead_letter(2).idle(IdlePolicy::Backoff { retries: 0 });
+        let mut total = 0;
+        let stats = worker.run(&mut con, &mut sum(&mut total)).unwrap();
+
+        assert_eq!(total, 3);
+        assert_eq!(stats, WorkerStats { recovered: 3, handled: 1, acked: 1, dead_lettered: 2, ..WorkerStats::default() });
+        let commands = con.commands();
+        assert_eq!(commands[1], "XPENDING s g 1-0 3-0 100 c");
+        // Delivered one time too many
+        assert!(commands[2].starts_with("XADD {s}:dlq * dlq-stream s dlq-group g dlq-consumer c dlq-id 1-0 dlq-deliveries 3 dlq-error delivered 3 times"));
+        assert!(commands[2].ends_with(" field:n 1"), "{}", commands[2]);
+        assert_eq!(commands[3], "XACK s g 1-0");
+        // Not a number
+        assert!(commands[4].contains("dlq-id 2-0 dlq-deliveries 1 dlq-error field 'n' of entry 2-0"), "{}", commands[4]);
+        assert_eq!(commands[6], "XACK s g 3-0");
+    }
+
+    #[test]
+    fn test_deleted_entries() {
+        // Entry 1-0 was trimmed while it was pending
+        let trimmed = Value::Bulk(vec![data("1-0"), Value::Nil]);
+        let number = Value::Bulk(vec![data("2-0"), Value::Bulk(vec![data("n"), data("2")])]);
+        let mut con = Replay::new(vec![
+            Value::Bulk(vec![Value::Bulk(vec![data("s"), Value::Bulk(vec![trimmed, number])])]),
+            Value::Int(1),
+            Value::Bulk(vec![Value::Bulk(vec![data("2-0"), data("c"), Value::Int(1000), Value::Int(2)])]),
+            Value::Int(1),
+            read(&[]),
+            Value::Nil,
+        ]);
+        let worker = GroupWorker::new("s", "g", "c").dead_letter(3).idle(IdlePolicy::Backoff { retries: 0 });
+        let mut total = 0;
+        let stats = worker.run(&mut con, &mut sum(&mut total)).unwrap();
+        assert_eq!(stats, WorkerStats { recovered: 2, handled: 1, acked: 1, deleted: 1, ..WorkerStats::default() });
+        let commands = con.commands();
+        assert_eq!(commands[1], "XACK s g 1-0");
+        // Only the entry that is left has its deliveries counted
+        assert_eq!(commands[2], "XPENDING s g 2-0 2-0 100 c");
+        assert_eq!(commands[3], "XACK s g 2-0");
+    }
+
+    #[test]
+    fn test_claim_deleted_entries_before_redis_7() {
+        let pending = |id: &str, consumer: &str| Value::Bulk(vec![data(id), data(consumer), Value::Int(90_000), Value::Int(1)]);
+        let mut replies = server("6.0.16");
+        replies.extend([
+            Value::Bulk(vec![pending("1-0", "gone"), pending("2-0", "gone")]),
+            // 1-0 was deleted, 2-0 was acknowledged by its consumer in the meantime
+            Value::Bulk(vec![Value::Nil]),
+            Value::Bulk(vec![pending("1-0", "c")]),
+            Value::Int(1),
+            Value::Nil,
+        ]);
+        let mut con = Replay::new(replies);
+        let worker = GroupWorker::new("s", "g", "c")
+            .recover_pending(false)
+            .claim_idle(Duration::from_secs(60), Duration::from_secs(10))
+            .idle(IdlePolicy::Backoff { retries: 0 });
+        let mut total = 0;
+        let stats = worker.run(&mut con, &mut sum(&mut total)).unwrap();
+        assert_eq!(stats, WorkerStats { deleted: 1, ..WorkerStats::default() });
+        let commands = con.commands();
+        assert_eq!(commands[4], "XPENDING s g 1-0 2-0 100 c");
+        assert_eq!(commands[5], "XACK s g 1-0");
+    }
+}
@@ -0,0 +1,253 @@
+//! Ranges of a stream given in time rather than IDs: an entry ID starts with the milliseconds
+//! since the epoch when the entry was added, so "between 09:00 and 10:00 yesterday" is the
+//! range from `<09:00 in ms>-0` to `<10:00 in ms>-0`, excluded.
+//!
+//! A bound is any of:
+//! - an entry ID, like `1700000000000-0`, or `-` and `+` for either end of the stream
+//! - milliseconds since the epoch, like `1700000000000`
+//! - an RFC 3339 time, like `2024-05-01T09:00:00Z` or `2024-05-01T11:00:00+02:00`
+//! - a local time: `2024-05-01 09:00`, `2024-05-01`, `09:00` (today), `yesterday 09:00`,
+//!   `today` or `yesterday` (at midnight), or `now`
+//! - a duration ago, like `5m`, `2h` or `1d ago`, see `parse_duration`
+//!
+//! Both bounds are included, unless they start with `(`, like XRANGE in Redis 6.2+.  The range
+//! is turned into complete IDs, so excluding a bound works with any version.
+
+use chrono::{DateTime, Local, LocalResult, NaiveDate, NaiveDateTime, NaiveTime, TimeZone, Utc};
+use clap_v3::{App, Arg, ArgMatches};
+
+use crate::{parse_duration, ConfigError, StreamId};
+
+/// The entries from `start` to `end`, both included
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TimeRange {
+    start: StreamId,
+    end: StreamId,
+}
+
+impl Default for TimeRange {
+    /// The whole stream
+    fn default() -> TimeRange {
+        TimeRange { start: StreamId::MIN, end: StreamId::MAX }
+    }
+}
+
+/// Which end of a range a bound is
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Side {
+    Start,
+    End,
+}
+
+impl TimeRange {
+    pub fn new(start: StreamId, end: StreamId) -> TimeRange {
+        TimeRange { start, end }
+    }
+
+    /// The entries added between two times, both included
+    pub fn between<Tz: TimeZone>(start: &DateTime<Tz>, end: &DateTime<Tz>) -> TimeRange {
+        TimeRange { start: time_id(start, Side::Start), end: time_id(end, Side::End) }
+    }
+
+    pub fn start(&self) -> StreamId {
+        self.start
+    }
+
+    pub fn end(&self) -> StreamId {
+        self.end
+    }
+
+    /// Start at the bound `since`, see the module documentation, with relative times from now
+    pub fn since(self, since: &str) -> Option<TimeRange> {
+        self.since_at(since, Local::now())
+    }
+
+    /// End at the bound `until`, see the module documentation, with relative times from now
+    pub fn until(self, until: &str) -> Option<TimeRange> {
+        self.until_at(until, Local::now())
+    }
+
+    /// Like `since`, with relative times from `now`
+    pub fn since_at(self, since: &str, now: DateTime<Local>) -> Option<TimeRange> {
+        Some(TimeRange { start: bound(since, Side::Start, now)?, ..self })
+    }
+
+    /// Like `until`, with relative times from `now`
+    pub fn until_at(self, until: &str, now: DateTime<Local>) -> Option<TimeRange> {
+        Some(TimeRange { end: bound(until, Side::End, now)?, ..self })
+    }
+
+    /// The range set with the arguments of `time_range_args`
+    pub fn from_matches(matches: &ArgMatches) -> Result<TimeRange, ConfigError> {
+        let mut range = TimeRange::default();
+        if let Some(since) = matches.value_of("SINCE") {
+            range = range.since(since).ok_or_else(|| ConfigError::from(format!("--since: '{}' is not a time or an ID", since)))?;
+        }
+        if let Some(until) = matches.value_of("UNTIL") {
+            range = range.until(until).ok_or_else(|| ConfigError::from(format!("--until: '{}' is not a time or an ID", until)))?;
+        }
+        Ok(range)
+    }
+}
+
+/// The ID of one end of a range, from a bound as described in the module documentation
+fn bound(s: &str, side: Side, now: DateTime<Local>) -> Option<StreamId> {
+    let s = s.trim();
+    let (s, exclusive) = match s.strip_prefix('(') {
+        Some(rest) => (rest.trim(), true),
+        None => (s, false),
+    };
+    // Excluding a time excludes every entry added during its millisecond
+    let time_side = match (exclusive, side) {
+        (false, side) => side,
+        (true, Side::Start) => Side::End,
+        (true, Side::End) => Side::Start,
+    };
+    let id = match s {
+        "-" => StreamId::MIN,
+        "+" => StreamId::MAX,
+        _ if s.contains('-') && s.parse::<StreamId>().is_ok() => s.parse().ok()?,
+        _ => time_id(&parse_time(s, now)?, time_side),
+    };
+    match (exclusive, side) {
+        (false, _) => Some(id),
+        (true, Side::Start) => id.next(),
+        (true, Side::End) => id.prev(),
+    }
+}
+
+/// The lowest or highest ID an entry added at `time` can have
+fn time_id<Tz: TimeZone>(time: &DateTime<Tz>, side: Side) -> StreamId {
+    let ms = time.timestamp_millis().max(0) as u64;
+    match side {
+        Side::Start => StreamId::new(ms, 0),
+        Side::End => StreamId::new(ms, u64::MAX),
+    }
+}
+
+/// A time as described in the module documentation, without the IDs, with relative times from `now`
+pub fn parse_time(s: &str, now: DateTime<Local>) -> Option<DateTime<Utc>> {
+    let s = s.trim();
+    if s.is_empty() {
+        return None;
+    }
+    if s == "now" {
+        return Some(now.with_timezone(&Utc));
+    }
+    if s.bytes().all(|b| b.is_ascii_digit()) {
+        return Utc.timestamp_millis_opt(s.parse().ok()?).single();
+    }
+    if let Ok(time) = DateTime::parse_from_rfc3339(s) {
+        return Some(time.with_timezone(&Utc));
+    }
+    if let Some(ago) = parse_duration(s.strip_suffix("ago").unwrap_or(s)) {
+        return Some(now.with_timezone(&Utc) - chrono::Duration::from_std(ago).ok()?);
+    }
+    local(s, now).map(|time| time.with_timezone(&Utc))
+}
+
+/// A local date and time, a date at midnight, or a time today or yesterday
+fn local(s: &str, now: DateTime<Local>) -> Option<DateTime<Local>> {
+    let today = now.date_naive();
+    let (date, time) = match s.split_once(' ').map_or((s, ""), |(date, time)| (date, time.trim())) {
+        ("today", time) => (today, time),
+        ("yesterday", time) => (today.pred_opt()?, time),
+        (date, time) => match NaiveDate::parse_from_str(date, "%Y-%m-%d") {
+            Ok(date) => (date, time),
+            // A time alone is today
+            Err(_) if time.is_empty() => (today, date),
+            Err(_) => return None,
+        },
+    };
+    let time = match time {
+        "" => NaiveTime::MIN,
+        time => NaiveTime::parse_from_str(time, "%H:%M:%S").or_else(|_| NaiveTime::parse_from_str(time, "%H:%M")).ok()?,
+    };
+    // The first of two times when clocks go back, and nothing for a time skipped when they go forward
+    match Local.from_local_datetime(&NaiveDateTime::new(date, time)) {
+        LocalResult::Single(time) | LocalResult::Ambiguous(time, _) => Some(time),
+        LocalResult::None => None,
+    }
+}
+
+/// Add `--since` and `--until` to the app of an example that reads a range of a stream,
+/// see `TimeRange::from_matches`
+pub fn time_range_args(app: App<'_>) -> App<'_> {
+    app.arg(
+        Arg::with_name("SINCE")
+            .help("The first entry: an ID, a time like 2024-05-01T09:00:00Z, 'yesterday 09:00' or 5m (ago), '(' to exclude it")
+            .long("since")
+            .value_name("TIME")
+            .takes_value(true),
+    )
+    .arg(
+        Arg::with_name("UNTIL")
+            .help("The last entry, like --since, e.g. '(yesterday 10:00' for the entries before 10:00 yesterday")
+            .long("until")
+            .value_name("TIME")
+            .takes_value(true),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 2024-05-02 12:30 local time
+    fn now() -> DateTime<Local> {
+        Local.with_ymd_and_hms(2024, 5, 2, 12, 30, 0).unwrap()
+    }
+
+    fn ms(time: DateTime<Local>) -> u64 {
+        time.timestamp_millis() as u64
+    }
+
+    #[test]
+    fn test_parse_time() {
+        let utc = |s| parse_time(s, now()).map(|time| time.timestamp_millis());
+        assert_eq!(utc("2024-05-01T09:00:00Z"), Some(1_714_554_000_000));
+        assert_eq!(utc("2024-05-01T11:00:00+02:00"), Some(1_714_554_000_000));
+        assert_eq!(utc("1714554000000"), Some(1_714_554_000_000));
+        assert_eq!(utc("now"), Some(now().timestamp_millis()));
+        assert_eq!(utc("5m"), Some(now().timestamp_millis() - 300_000));
+        assert_eq!(utc("1d ago"), Some(now().timestamp_millis() - 86_400_000));
+
+        let local = |y, m, d, h, min| Some(Local.with_ymd_and_hms(y, m, d, h, min, 0).unwrap().timestamp_millis());
+        assert_eq!(utc("2024-05-01 09:00"), local(2024, 5, 1, 9, 0));
+        assert_eq!(utc("2024-05-01"), local(2024, 5, 1, 0, 0));
+        assert_eq!(utc("yesterday 09:15"), local(2024, 5, 1, 9, 15));
+        assert_eq!(utc("today"), local(2024, 5, 2, 0, 0));
+        assert_eq!(utc("08:00"), local(2024, 5, 2, 8, 0));
+
+        assert_eq!(utc("tomorrow"), None);
+        assert_eq!(utc("2024-05-01 25:00"), None);
+        assert_eq!(utc(""), None);
+    }
+
+    #[test]
+    fn test_bounds() {
+        let nine = Local.with_ymd_and_hms(2024, 5, 1, 9, 0, 0).unwrap();
+        let ten = Local.with_ymd_and_hms(2024, 5, 1, 10, 0, 0).unwrap();
+
+        let range = TimeRange::default().since_at("yesterday 09:00", now()).unwrap().until_at("(yesterday 10:00", now()).unwrap();
+        assert_eq!(range, TimeRange::new(StreamId::new(ms(nine), 0), StreamId::new(ms(ten) - 1, u64::MAX)));
+        assert_eq!(TimeRange::between(&nine, &ten).end(), StreamId::new(ms(ten), u64::MAX));
+
+        let range = TimeRange::default().since_at("(1714554000000-3", now()).unwrap().until_at("+", now()).unwrap();
+        assert_eq!(range, TimeRange::new(StreamId::new(1_714_554_000_000, 4), StreamId::MAX));
+        let range = TimeRange::default().since_at("(2024-05-01T09:00:00Z", now()).unwrap();
+        assert_eq!(range.start(), StreamId::new(1_714_554_000_001, 0));
+        assert_eq!(TimeRange::default().since_at("-", now()).unwrap(), TimeRange::default());
+
+        assert_eq!(TimeRange::default().until_at("(0-0", now()), None);
+        assert_eq!(TimeRange::default().since_at("soon", now()), None);
+    }
+
+    #[test]
+    fn test_from_matches() {
+        let matches = time_range_args(App::new("range")).try_get_matches_from(["range", "--since", "1714554000000-0"]).unwrap();
+        assert_eq!(TimeRange::from_matches(&matches).unwrap(), TimeRange::new(StreamId::new(1_714_554_000_000, 0), StreamId::MAX));
+        let matches = time_range_args(App::new("range")).try_get_matches_from(["range", "--until", "later"]).unwrap();
+        assert!(TimeRange::from_matches(&matches).is_err());
+    }
+}
@@ -0,0 +1,366 @@
+//! Windows over the numbers in a stream, and what to compute on them: the minimum, maximum,
+//! mean, median, percentiles, standard deviation and exponentially weighted moving average.
+//!
+//! A window spans a number of samples or a while, measured by the milliseconds of the entry
+//! IDs rather than the clock, so that reading a stream again gives the same windows.  A sliding
+//! window always holds the latest samples, a tumbling window fills up, is handed back by
+//! `push` once it is complete and starts over empty.  `KeyedWindows` keeps a window per key,
+//! e.g. per postal code.
+//!
+//! ```
+//! use rs_util::window::{Span, Window};
+//! use rs_util::StreamId;
+//!
+//! let mut window = Window::sliding(Span::Count(3));
+//! for (ms, temp) in [(1, 50), (2, 52), (3, 51), (4, 60)] {
+//!     window.push(StreamId::new(ms, 0), temp);
+//! }
+//! assert_eq!(window.mean(), Some(163.0 / 3.0));
+//! assert_eq!(window.max(), Some(60));
+//! ```
+
+use std::collections::{HashMap, VecDeque};
+use std::hash::Hash;
+use std::mem;
+use std::time::Duration;
+
+use crate::StreamId;
+
+/// A number that can be put in a window.  The aggregates are computed in `f64`, so a sum
+/// doesn't overflow, whatever the type of the samples.
+pub trait Number: Copy + PartialOrd {
+    fn to_f64(self) -> f64;
+}
+
+macro_rules! number {
+    ($($t:ty),*) => {
+        $(impl Number for $t {
+            fn to_f64(self) -> f64 {
+                self as f64
+            }
+        })*
+    };
+}
+
+number!(i8, i16, i32, i64, i128, isize, u8, u16, u32, u64, u128, usize, f32, f64);
+
+/// How much a window holds
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Span {
+    /// This many samples
+    Count(usize),
+    /// The samples of entries added within this while of each other, by their IDs
+    Time(Duration),
+}
+
+/// A sliding or tumbling window of samples, see the module documentation
+#[derive(Clone, Debug)]
+pub struct Window<T> {
+    span: Span,
+    tumbling: bool,
+    samples: VecDeque<(StreamId, T)>,
+}
+
+impl<T: Number> Window<T> {
+    /// A window of the latest samples within `span`
+    pub fn sliding(span: Span) -> Window<T> {
+        Window { span, tumbling: false, samples: VecDeque::new() }
+    }
+
+    /// A window that is handed back by `push` once `span` is full, and then starts over.  A
+    /// time window covers a multiple of its duration since the epoch, e.g. 10:00 to 10:05.
+    pub fn tumbling(span: Span) -> Window<T> {
+        Window { span, tumbling: true, samples: VecDeque::new() }
+    }
+
+    /// An empty window like this one
+    fn empty(&self) -> Window<T> {
+        Window { span: self.span, tumbling: self.tumbling, samples: VecDeque::new() }
+    }
+
+    /// Add the sample of the entry `id`, in the order of the IDs.  A tumbling window returns
+    /// the window that `value` completed, or that it is too late for: then `value` starts the
+    /// next one.
+    pub fn push(&mut self, id: StreamId, value: T) -> Option<Window<T>> {
+        if self.tumbling {
+            return self.push_tumbling(id, value);
+        }
+        self.samples.push_back((id, value));
+        match self.span {
+            Span::Count(n) => {
+                while self.samples.len() > n {
+                    self.samples.pop_front();
+                }
+            }
+            Span::Time(d) => {
+                // Less than a millisecond apart is the same millisecond
+                let span = (d.as_millis() as u64).max(1);
+                while self.samples.front().is_some_and(|(first, _)| id.ms.saturating_sub(first.ms) >= span) {
+                    self.samples.pop_front();
+                }
+            }
+        }
+        None
+    }
+
+    fn push_tumbling(&mut self, id: StreamId, value: T) -> Option<Window<T>> {
+        match self.span {
+            Span::Count(n) => {
+                self.samples.push_back((id, value));
+                (self.samples.len() >= n).then(|| self.close()).flatten()
+            }
+            Span::Time(d) => {
+                let slot = |ms: u64| ms / (d.as_millis() as u64).max(1);
+                let closed = match self.samples.front() {
+                    Some((first, _)) if slot(id.ms) != slot(first.ms) => self.close(),
+                    _ => None,
+                };
+                self.samples.push_back((id, value));
+                closed
+            }
+        }
+    }
+
+    /// Hand back the samples so far and start over, e.g. for the last tumbling window of a
+    /// stream.  `None` if the window is empty.
+    pub fn close(&mut self) -> Option<Window<T>> {
+        if self.samples.is_empty() {
+            return None;
+        }
+        let empty = self.empty();
+        Some(mem::replace(self, empty))
+    }
+
+    pub fn len(&self) -> usize {
+        self.samples.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.samples.is_empty()
+    }
+
+    /// The samples, oldest first
+    pub fn values(&self) -> impl Iterator<Item = T> + '_ {
+        self.samples.iter().map(|(_, value)| *value)
+    }
+
+    /// The IDs of the first and last entries in the window
+    pub fn ids(&self) -> Option<(StreamId, StreamId)> {
+        Some((self.samples.front()?.0, self.samples.back()?.0))
+    }
+
+    pub fn min(&self) -> Option<T> {
+        self.values().fold(None, |min, value| match min {
+            Some(min) if min <= value => Some(min),
+            _ => Some(value),
+        })
+    }
+
+    pub fn max(&self) -> Option<T> {
+        self.values().fold(None, |max, value| match max {
+            Some(max) if max >= value => Some(max),
+            _ => Some(value),
+        })
+    }
+
+    pub fn sum(&self) -> f64 {
+        self.values().map(Number::to_f64).sum()
+    }
+
+    pub fn mean(&self) -> Option<f64> {
+        (!self.is_empty()).then(|| self.sum() / self.len() as f64)
+    }
+
+    pub fn median(&self) -> Option<f64> {
+        self.percentile(50.0)
+    }
+
+    /// The value below which `p` percent of the samples are, interpolating between the two
+    /// nearest samples, e.g. `percentile(99.0)`
+    pub fn percentile(&self, p: f64) -> Option<f64> {
+        if self.is_empty() || !(0.0..=100.0).contains(&p) {
+            return None;
+        }
+        let mut sorted: Vec<f64> = self.values().map(Number::to_f64).collect();
+        sorted.sort_by(f64::total_cmp);
+        let rank = p / 100.0 * (sorted.len() - 1) as f64;
+        let (below, above) = (sorted[rank.floor() as usize], sorted[rank.ceil() as usize]);
+        Some(below + (above - below) * rank.fract())
+    }
+
+    /// The population standard deviation: how far the samples are from their mean
+    pub fn stddev(&self) -> Option<f64> {
+        let mean = self.mean()?;
+        let variance = self.values().map(|value| (value.to_f64() - mean).powi(2)).sum::<f64>() / self.len() as f64;
+        Some(variance.sqrt())
+    }
+
+    /// The exponentially weighted moving average of the samples, oldest first, see `Ewma`
+    pub fn ewma(&self, alpha: f64) -> Option<f64> {
+        let mut ewma = Ewma::new(alpha);
+        self.values().for_each(|value| {
+            ewma.push(value);
+        });
+        ewma.value()
+    }
+}
+
+/// An exponentially weighted moving average, which follows every sample without a window:
+/// each sample weighs `alpha`, between 0 and 1, and what came before `1 - alpha`
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Ewma {
+    alpha: f64,
+    value: Option<f64>,
+}
+
+impl Ewma {
+    pub fn new(alpha: f64) -> Ewma {
+        Ewma { alpha: alpha.clamp(0.0, 1.0), value: None }
+    }
+
+    /// Add a sample, and return the new average.  The first sample is the average.
+    pub fn push<T: Number>(&mut self, value: T) -> f64 {
+        let value = value.to_f64();
+        let average = match self.value {
+            Some(average) => self.alpha * value + (1.0 - self.alpha) * average,
+            None => value,
+        };
+        self.value = Some(average);
+        average
+    }
+
+    pub fn value(&self) -> Option<f64> {
+        self.value
+    }
+}
+
+/// A window per key, all alike, e.g. the temperatures per postal code
+#[derive(Clone, Debug)]
+pub struct KeyedWindows<K, T> {
+    template: Window<T>,
+    windows: HashMap<K, Window<T>>,
+}
+
+impl<K: Eq + Hash + Clone, T: Number> KeyedWindows<K, T> {
+    /// A window like `template`, which should be empty, for each key
+    pub fn new(template: Window<T>) -> KeyedWindows<K, T> {
+        KeyedWindows { template: template.empty(), windows: HashMap::new() }
+    }
+
+    /// Add a sample to the window of `key`, see `Window::push`
+    pub fn push(&mut self, key: &K, id: StreamId, value: T) -> Option<Window<T>> {
+        if !self.windows.contains_key(key) {
+            self.windows.insert(key.clone(), self.template.empty());
+        }
+        self.windows.get_mut(key)?.push(id, value)
+    }
+
+    pub fn get(&self, key: &K) -> Option<&Window<T>> {
+        self.windows.get(key)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&K, &Window<T>)> {
+        self.windows.iter()
+    }
+
+    /// Close the window of every key, see `Window::close`
+    pub fn close(&mut self) -> Vec<(K, Window<T>)> {
+        self.windows.iter_mut().filter_map(|(key, window)| Some((key.clone(), window.close()?))).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn id(ms: u64) -> StreamId {
+        StreamId::new(ms, 0)
+    }
+
+    fn values<T: Number>(window: &Window<T>) -> Vec<T> {
+        window.values().collect()
+    }
+
+    #[test]
+    fn test_sliding() {
+        let mut count = Window::sliding(Span::Count(3));
+        for (ms, value) in [(1, 1), (2, 2), (3, 3), (4, 4)] {
+            assert!(count.push(id(ms), value).is_none());
+        }
+        assert_eq!(values(&count), vec![2, 3, 4]);
+        assert_eq!(count.ids(), Some((id(2), id(4))));
+
+        // The last second, by the IDs
+        let mut time = Window::sliding(Span::Time(Duration::from_secs(1)));
+        for ms in [0, 500, 999, 1000, 1999] {
+            time.push(id(ms), ms);
+        }
+        assert_eq!(values(&time), vec![1000, 1999]);
+    }
+
+    #[test]
+    fn test_tumbling() {
+        let mut count = Window::tumbling(Span::Count(2));
+        assert!(count.push(id(1), 1.5).is_none());
+        assert_eq!(values(&count.push(id(2), 2.5).unwrap()), vec![1.5, 2.5]);
+        assert!(count.is_empty());
+        assert!(count.push(id(3), 3.5).is_none());
+        assert_eq!(values(&count.close().unwrap()), vec![3.5]);
+        assert!(count.close().is_none());
+
+        // Seconds since the epoch
+        let mut time = Window::tumbling(Span::Time(Duration::from_secs(1)));
+        let closed: Vec<Vec<u64>> = [100, 900, 1000, 1500, 3200].into_iter().filter_map(|ms| time.push(id(ms), ms)).map(|w| values(&w)).collect();
+        assert_eq!(closed, vec![vec![100, 900], vec![1000, 1500]]);
+        assert_eq!(values(&time), vec![3200]);
+    }
+
+    #[test]
+    fn test_aggregates() {
+        let mut window = Window::sliding(Span::Count(10));
+        assert_eq!((window.min(), window.mean(), window.median(), window.stddev()), (None, None, None, None));
+        for (ms, value) in [(1, 2), (2, 4), (3, 4), (4, 4), (5, 5), (6, 5), (7, 7), (8, 9)] {
+            window.push(id(ms), value);
+        }
+        assert_eq!((window.min(), window.max()), (Some(2), Some(9)));
+        assert_eq!(window.mean(), Some(5.0));
+        assert_eq!(window.median(), Some(4.5));
+        assert_eq!(window.percentile(0.0), Some(2.0));
+        assert_eq!(window.percentile(100.0), Some(9.0));
+        assert_eq!(window.percentile(25.0), Some(4.0));
+        assert_eq!(window.percentile(101.0), None);
+        assert_eq!(window.stddev(), Some(2.0));
+
+        // Big numbers don't overflow the sum
+        let mut big = Window::sliding(Span::Count(2));
+        big.push(id(1), i32::MAX);
+        big.push(id(2), i32::MAX);
+        assert_eq!(big.mean(), Some(i32::MAX as f64));
+    }
+
+    #[test]
+    fn test_ewma() {
+        let mut ewma = Ewma::new(0.5);
+        assert_eq!(ewma.value(), None);
+        assert_eq!(ewma.push(10), 10.0);
+        assert_eq!(ewma.push(20), 15.0);
+        assert_eq!(ewma.push(20u8), 17.5);
+
+        let mut window = Window::sliding(Span::Count(2));
+        for (ms, value) in [(1, 10), (2, 20), (3, 30)] {
+            window.push(id(ms), value);
+        }
+        assert_eq!(window.ewma(0.5), Some(25.0));
+    }
+
+    #[test]
+    fn test_keyed() {
+        let mut windows = KeyedWindows::new(Window::tumbling(Span::Count(2)));
+        assert!(windows.push(&94016, id(1), 50).is_none());
+        assert!(windows.push(&80014, id(2), 70).is_none());
+        assert_eq!(windows.push(&94016, id(3), 52).unwrap().mean(), Some(51.0));
+        assert_eq!(windows.get(&80014).map(values), Some(vec![70]));
+        assert_eq!(windows.close().len(), 1);
+        assert!(windows.iter().all(|(_, window)| window.is_empty()));
+    }
+}
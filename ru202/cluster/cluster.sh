@@ -0,0 +1,70 @@
+#!/bin/bash
+
+# Local Cluster Helper
+#
+# Start a Redis Cluster of three masters, each with one replica, on ports 30001-30006
+# (or starting at $PORT), and keep it running until Ctrl-C.
+# Point any of the stream labs at it with the seed nodes printed below, e.g.
+#
+#     ./range_1 --cluster 127.0.0.1:30001 --cluster 127.0.0.1:30002
+#
+# Requires redis-server and redis-cli on the PATH.
+
+PORT=${PORT:-30001}
+NODES=6
+REPLICAS=1
+
+for cmd in redis-server redis-cli; do
+    if ! command -v $cmd > /dev/null; then
+        echo "[ERROR] $cmd was not found on the PATH."
+        exit 1
+    fi
+done
+
+CLUSTER_DIR=$(mktemp -d -t redis-cluster.XXXXXX)
+PIDS=()
+
+# Stop the nodes and remove their files, however the helper ends
+cleanup() {
+    echo -e "\n[>] Stopping the cluster..."
+    for pid in "${PIDS[@]}"; do
+        kill "$pid" 2> /dev/null
+    done
+    wait 2> /dev/null
+    rm -rf "$CLUSTER_DIR"
+}
+trap cleanup EXIT
+
+ADDRESSES=()
+for i in $(seq 0 $((NODES - 1))); do
+    port=$((PORT + i))
+    echo "[>] Starting a cluster node on port $port..."
+    mkdir -p "$CLUSTER_DIR/$port"
+    redis-server --port "$port" --dir "$CLUSTER_DIR/$port" \
+        --cluster-enabled yes --cluster-config-file nodes.conf --cluster-node-timeout 5000 \
+        --save "" --appendonly no > "$CLUSTER_DIR/$port.log" 2>&1 &
+    PIDS+=($!)
+    ADDRESSES+=("127.0.0.1:$port")
+done
+
+# Wait for every node to answer before joining them
+for address in "${ADDRESSES[@]}"; do
+    until redis-cli -p "${address##*:}" PING > /dev/null 2>&1; do
+        sleep 0.1
+    done
+done
+
+echo "[>] Creating the cluster..."
+redis-cli --cluster create "${ADDRESSES[@]}" --cluster-replicas $REPLICAS --cluster-yes || exit
+
+# The cluster is usable once every slot is covered
+until redis-cli -p "$PORT" CLUSTER INFO | grep -q "cluster_state:ok"; do
+    sleep 0.5
+done
+
+echo -e "\n\n#### Local Cluster ###\n"
+echo "Seed nodes: --cluster 127.0.0.1:$PORT --cluster 127.0.0.1:$((PORT + 1))"
+echo "       or:  export REDIS_CLUSTER=127.0.0.1:$PORT,127.0.0.1:$((PORT + 1))"
+echo -e "\nPress Ctrl-C to stop the cluster."
+
+wait
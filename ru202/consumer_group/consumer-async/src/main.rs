@@ -0,0 +1,22 @@
+use std::process::ExitCode;
+
+use clap_v3::Arg;
+use rrbe::streams::consumer_group_consumer_async;
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    let matches = rs_util::app("ru202-consumer-group-consumer-async", consumer_group_consumer_async::ABOUT)
+        .arg(Arg::with_name("STREAM").help("Stream name").required(true))
+        .arg(Arg::with_name("GROUP").help("Consumer group name").required(true))
+        .arg(Arg::with_name("CONSUMER").help("Consumer instance name").required(true))
+        .get_matches();
+    let config = rs_util::config_or_exit(&matches);
+
+    let result = consumer_group_consumer_async::run(
+        &config,
+        matches.value_of("STREAM").unwrap(),
+        matches.value_of("GROUP").unwrap(),
+        matches.value_of("CONSUMER").unwrap(),
+    ).await;
+    rs_util::exit_code(result)
+}
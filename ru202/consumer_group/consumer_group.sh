@@ -20,6 +20,15 @@ if [[ -d ./consumer ]]; then
     echo -e "\n\n"
 fi
 
+# If the consumer-async directory exists, build consumer_group_consumer_async
+if [[ -d ./consumer-async ]]; then
+    echo "[>] Building consumer_group_consumer_async..."
+    cd consumer-async
+    cargo build || exit
+    cd ..
+    echo -e "\n\n"
+fi
+
 # If the bin directory does not exist, create it.
 if [[ ! -d ./bin ]]; then
     mkdir bin
@@ -30,6 +39,7 @@ fi
 # copy the executables into bin.
 MAIN="./main/target/debug/consumer_group_main"
 CONSUMER="./consumer/target/debug/consumer_group_consumer"
+CONSUMER_ASYNC="./consumer-async/target/debug/consumer_group_consumer_async"
 
 if [[ -f "$MAIN" ]]; then
     cp -v "$MAIN" ./bin/
@@ -39,7 +49,11 @@ if [[ -f "$CONSUMER" ]]; then
     cp -v "$CONSUMER" ./bin/
 fi
 
-# Run the lab by executing consumer_group_main
+if [[ -f "$CONSUMER_ASYNC" ]]; then
+    cp -v "$CONSUMER_ASYNC" ./bin/
+fi
+
+# Run the lab by executing consumer_group_main, e.g. ./consumer_group.sh --chaos remove
 echo -e "\n\n#### Consumer Group Lab ###\n\n"
 cd bin
-./consumer_group_main
+./consumer_group_main "$@"
@@ -0,0 +1,98 @@
+#!/bin/bash
+
+# Sentinel Failover Lab
+#
+# Start a master, a replica and three Sentinels on random ports, run producer_1 against the
+# master through the Sentinels, then kill the master.  The Sentinels promote the replica and
+# producer_1 carries on writing to it.
+#
+# Requires redis-server, redis-sentinel and redis-cli on the PATH.
+# This is a lab, so everything is built with the debug profile.
+
+MASTER_NAME="lab-master"
+
+for cmd in redis-server redis-sentinel redis-cli; do
+    if ! command -v $cmd > /dev/null; then
+        echo "[ERROR] $cmd was not found on the PATH."
+        exit 1
+    fi
+done
+
+echo "[>] Building producer_1..."
+(cd ../producer_1 && cargo build) || exit
+PRODUCER="../producer_1/target/debug/producer_1"
+echo -e "\n\n"
+
+# Pick five distinct random ports: master, replica and three Sentinels
+PORTS=($(shuf -i 20000-29999 -n 5))
+MASTER_PORT=${PORTS[0]}
+REPLICA_PORT=${PORTS[1]}
+SENTINEL_PORTS=(${PORTS[2]} ${PORTS[3]} ${PORTS[4]})
+
+LAB_DIR=$(mktemp -d -t failover-lab.XXXXXX)
+PIDS=()
+
+# Stop everything we started, however the lab ends
+cleanup() {
+    echo -e "\n[>] Stopping the lab..."
+    for pid in "${PIDS[@]}"; do
+        kill "$pid" 2> /dev/null
+    done
+    wait 2> /dev/null
+    rm -rf "$LAB_DIR"
+}
+trap cleanup EXIT
+
+start_server() {
+    local port=$1; shift
+    mkdir -p "$LAB_DIR/$port"
+    redis-server --port "$port" --dir "$LAB_DIR/$port" --save "" --appendonly no "$@" \
+        > "$LAB_DIR/$port.log" 2>&1 &
+    PIDS+=($!)
+}
+
+echo "[>] Starting the master on port $MASTER_PORT and the replica on port $REPLICA_PORT..."
+start_server "$MASTER_PORT"
+MASTER_PID=$!
+start_server "$REPLICA_PORT" --replicaof 127.0.0.1 "$MASTER_PORT"
+
+for port in "${SENTINEL_PORTS[@]}"; do
+    echo "[>] Starting a Sentinel on port $port..."
+    cat > "$LAB_DIR/sentinel-$port.conf" <<CONF
+port $port
+sentinel monitor $MASTER_NAME 127.0.0.1 $MASTER_PORT 2
+sentinel down-after-milliseconds $MASTER_NAME 2000
+sentinel failover-timeout $MASTER_NAME 10000
+CONF
+    redis-sentinel "$LAB_DIR/sentinel-$port.conf" > "$LAB_DIR/sentinel-$port.log" 2>&1 &
+    PIDS+=($!)
+done
+
+# Give the servers and Sentinels a moment to find each other
+sleep 3
+
+SENTINEL_ARGS=()
+for port in "${SENTINEL_PORTS[@]}"; do
+    SENTINEL_ARGS+=(--sentinel "127.0.0.1:$port")
+done
+
+echo -e "\n\n#### Sentinel Failover Lab ###\n\n"
+"$PRODUCER" "${SENTINEL_ARGS[@]}" --master-name "$MASTER_NAME" &
+PIDS+=($!)
+
+sleep 5
+echo -e "\n[>] Killing the master on port $MASTER_PORT...\n"
+kill -9 "$MASTER_PID"
+
+# Wait for the Sentinels to promote the replica
+for _ in $(seq 1 30); do
+    sleep 1
+    ADDR=$(redis-cli -p "${SENTINEL_PORTS[0]}" SENTINEL GET-MASTER-ADDR-BY-NAME "$MASTER_NAME" | tr '\n' ':')
+    if [[ "$ADDR" == "127.0.0.1:$REPLICA_PORT:" ]]; then
+        echo -e "\n[>] The Sentinels promoted the replica on port $REPLICA_PORT to master.\n"
+        break
+    fi
+done
+
+# Let producer_1 show that it carries on writing to the new master
+sleep 5
@@ -1,18 +1,8 @@
-use std::error::Error;
+use std::process::ExitCode;
 
-use redis::Commands;
+use rrbe::streams::helloworld;
 
-fn main() -> Result<(), Box<dyn Error>> {
-    let app_name = String::from("ru202-hello-world");
-    let about = String::from(
-        "
-    Redis University 202 - Streams: Helloworld
-            A very simple app that assumes there is a key named hello already
-            in the redis DB.  It gets this value and displays its value on the screen.",
-    );
-    let config = rs_util::app_config(app_name, about);
-    let mut con = rs_util::get_connection(&config)?;
-    let resp: String = con.get("hello")?;
-    println!("{}", resp);
-    Ok(())
-}
\ No newline at end of file
+fn main() -> ExitCode {
+    let config = rs_util::app_config(String::from("ru202-hello-world"), String::from(helloworld::ABOUT));
+    rs_util::exit_code(helloworld::run(&config))
+}
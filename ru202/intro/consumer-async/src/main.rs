@@ -0,0 +1,9 @@
+use std::process::ExitCode;
+
+use rrbe::streams::intro_consumer_async;
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    let config = rs_util::app_config(String::from("ru202-intro-consumer-async"), String::from(intro_consumer_async::ABOUT));
+    rs_util::exit_code(intro_consumer_async::run(&config).await)
+}
@@ -0,0 +1,16 @@
+use std::process::ExitCode;
+
+use rrbe::streams::intro_producer_async;
+use rs_util::Retention;
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    let matches = rs_util::retention_args(rs_util::app("ru202-intro-producer-async", intro_producer_async::ABOUT)).get_matches();
+    let config = rs_util::config_or_exit(&matches);
+
+    let retention = match Retention::from_matches(&matches) {
+        Ok(retention) => retention,
+        Err(e) => return rs_util::exit_code(Err(e.into())),
+    };
+    rs_util::exit_code(intro_producer_async::run(&config, retention).await)
+}
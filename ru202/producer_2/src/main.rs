@@ -1,39 +1,15 @@
-use std::error::Error;
-/// Natural Numbers Stream Producer: 0 to 100
-/// The stream is removed before each run.
-/// Each run begins at 0 and ends at 100.
+use std::process::ExitCode;
 
-use chrono::prelude::*;
-use redis::Commands;
+use rrbe::streams::producer_2;
+use rs_util::Retention;
 
-fn main() -> Result<(), Box<dyn Error>> {
-    let app_name = String::from("ru202-producer-2");
-    let about = String::from(
-        "
-    Redis University 202 - Streams: producer_2
-            A very simple app to write the natural numbers from 0 to 100
-            to a stream named numbers.",
-    );
-    let config = rs_util::app_config(app_name, about);
-    let mut con = rs_util::get_connection(&config)?;
-    let stream_name = "numbers";
+fn main() -> ExitCode {
+    let matches = rs_util::retention_args(rs_util::app("ru202-producer-2", producer_2::ABOUT)).get_matches();
+    let config = rs_util::config_or_exit(&matches);
 
-    // Make sure the stream does not exist before writing data to it
-    let _: u8 = con.del(stream_name)
-        .expect(&format!("[ERROR] Failure deleting stream {}", stream_name));
-    
-    for n in 0..=100 {
-        // Write data to stream
-        let id: String = con
-            .xadd(stream_name, "*", &[("n".to_string(), n.to_string())])
-            .expect(&format!(
-                "[ERROR] Failure writing number {} to stream: {}",
-                n, stream_name
-            ));
-        let dt = Local::now();
-        println!("{}: Produced the number {} as message ID {}",
-            dt.format("%T"), n, id);
-    }
-
-    Ok(())
+    let retention = match Retention::from_matches(&matches) {
+        Ok(retention) => retention,
+        Err(e) => return rs_util::exit_code(Err(e.into())),
+    };
+    rs_util::exit_code(producer_2::run(&config, retention))
 }
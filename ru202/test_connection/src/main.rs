@@ -1,15 +1,14 @@
-use std::error::Error;
+use std::process::ExitCode;
 
-fn main() -> Result<(), Box<dyn Error>> {
-    let app_name = String::from("ru202-test-connection");
-    let about = String::from(
-        "
-    Redis University 202 - Streams: test_connection
-            A very simple app that executes redis' ping and prints the response.",
-    );
-    let config = rs_util::app_config(app_name, about);
-    let mut con = rs_util::get_connection(&config)?;
-    let resp: String = redis::cmd("PING").query(&mut con).unwrap();
-    println!("{}", resp);
-    Ok(())
-}
\ No newline at end of file
+use clap_v3::Arg;
+use rrbe::streams::test_connection::{self, Output};
+
+fn main() -> ExitCode {
+    let matches = rs_util::app("ru202-test-connection", test_connection::ABOUT)
+        .arg(Arg::with_name("json").long("json").help("Print the report as JSON"))
+        .get_matches();
+    let config = rs_util::config_or_exit(&matches);
+
+    let output = if matches.is_present("json") { Output::Json } else { Output::Text };
+    rs_util::exit_code(test_connection::run(&config, output))
+}